@@ -0,0 +1,39 @@
+/* C interface of brainfuck-interpreter-rs, built with `cargo build --release --features ffi` */
+
+#ifndef BRAINFUCK_H
+#define BRAINFUCK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return codes of bf_interpreter_run */
+#define BF_OK 0
+#define BF_ERROR 1
+#define BF_INVALID_ARGUMENT 2
+#define BF_PANIC 3
+
+typedef struct BFInterpreter BFInterpreter;
+
+/* Creates an interpreter owned by the caller, or returns NULL. Free it with bf_interpreter_free. */
+BFInterpreter *bf_interpreter_new(void);
+
+/* Runs the nul-terminated program with input_len bytes of input, input may be NULL when input_len is 0.
+   `,` reads 0 once the input is exhausted. The input is only borrowed for the call. */
+int bf_interpreter_run(BFInterpreter *interpreter, const char *source, const uint8_t *input, size_t input_len);
+
+/* Returns the output of the last run, not nul-terminated, and writes its length to len.
+   Owned by the interpreter, valid until the next run or until it's freed. */
+const uint8_t *bf_interpreter_output(const BFInterpreter *interpreter, size_t *len);
+
+/* Frees the interpreter, does nothing with NULL */
+void bf_interpreter_free(BFInterpreter *interpreter);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
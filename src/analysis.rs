@@ -0,0 +1,284 @@
+//! Static analyses over the parsed instruction stream
+//!
+//! These never run the program, so they're necessarily conservative: they only
+//! report what can be proven from the instructions alone.
+
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use crate::Instruction;
+
+/// Returns the indices of instructions that can never be executed
+/// 
+/// Tracks the value of the current cell through straight-line code: a loop entered
+/// on a cell known to be 0 is skipped entirely, and an empty loop entered on a cell
+/// known to be non-zero never exits, so nothing after it runs.
+pub(crate) fn unreachable_instructions(instructions: &[Instruction], jump_table: &[usize], zeroed_tape: bool) -> Vec<usize> {
+    let mut unreachable = Vec::new();
+    let mut cell = match zeroed_tape {
+        true => Some(0u8),
+        false => None,
+    };
+    let mut i = 0;
+
+    while i < instructions.len() {
+        match instructions[i] {
+            Instruction::OpenLoop => {
+                let end = jump_table[i];
+
+                match cell {
+                    Some(0) => {
+                        unreachable.extend(i + 1..=end);
+                        i = end;
+                    },
+                    Some(_) if end == i + 1 => {
+                        unreachable.extend(end + 1..instructions.len());
+                        break;
+                    },
+                    // The value is only known on the first iteration
+                    _ => cell = None,
+                }
+            },
+            Instruction::CloseLoop | Instruction::Clear | Instruction::Scan(_) | Instruction::Cat => cell = Some(0),
+            Instruction::ByteInc => cell = cell.map(|v| v.wrapping_add(1)),
+            Instruction::ByteDec => cell = cell.map(|v| v.wrapping_sub(1)),
+            Instruction::AddByte(n) => cell = cell.map(|v| v.wrapping_add(n)),
+            Instruction::SetByte(n) => cell = Some(n),
+            Instruction::PointerInc | Instruction::PointerDec | Instruction::MovePointer(_) | Instruction::Input
+                | Instruction::PeekInput | Instruction::Random => cell = None,
+            Instruction::Output | Instruction::DumpTape | Instruction::Signal | Instruction::PrintPointer
+                | Instruction::MulAdd { .. } => (),
+        }
+
+        i += 1;
+    }
+
+    unreachable
+}
+
+/// Returns the indices of the `[` of loops that may never terminate
+/// 
+/// Only loops without nested loops, input or random bytes are considered. Such a
+/// loop ends up on the cell it started on every iteration if its pointer moves
+/// cancel out, and then it only terminates for every starting value if each
+/// iteration changes that cell by an odd amount. Loops that change it by an even
+/// amount, including not at all, are reported.
+pub(crate) fn possibly_infinite_loops(instructions: &[Instruction], jump_table: &[usize]) -> Vec<usize> {
+    let mut loops = Vec::new();
+
+    for (start, &instruction) in instructions.iter().enumerate() {
+        if instruction != Instruction::OpenLoop {
+            continue;
+        }
+
+        let mut offset: isize = 0;
+        let mut delta: u8 = 0;
+        let mut known = true;
+
+        for &instruction in &instructions[start + 1..jump_table[start]] {
+            match instruction {
+                Instruction::PointerInc => offset += 1,
+                Instruction::PointerDec => offset -= 1,
+                Instruction::MovePointer(n) => offset += n,
+                Instruction::ByteInc if offset == 0 => delta = delta.wrapping_add(1),
+                Instruction::ByteDec if offset == 0 => delta = delta.wrapping_sub(1),
+                Instruction::AddByte(n) if offset == 0 => delta = delta.wrapping_add(n),
+                Instruction::Clear | Instruction::SetByte(_) if offset == 0 => {
+                    known = false;
+                    break;
+                },
+                Instruction::ByteInc | Instruction::ByteDec | Instruction::AddByte(_) | Instruction::Clear
+                    | Instruction::SetByte(_) | Instruction::Output | Instruction::DumpTape | Instruction::Signal
+                    | Instruction::PrintPointer | Instruction::MulAdd { .. } => (),
+                Instruction::OpenLoop | Instruction::CloseLoop | Instruction::Input | Instruction::PeekInput
+                    | Instruction::Random | Instruction::Scan(_) | Instruction::Cat => {
+                    known = false;
+                    break;
+                },
+            }
+        }
+
+        if known && offset == 0 && delta.is_multiple_of(2) {
+            loops.push(start);
+        }
+    }
+
+    loops
+}
+
+/// Cell values known at some point of the program, relative to the pointer at the start
+/// of the tracked region
+pub(crate) struct Cells {
+    pub(crate) pointer: isize,
+    pub(crate) values: HashMap<isize, Option<u8>>,
+    /// Value of the cells missing from `values`
+    pub(crate) default: Option<u8>,
+}
+
+impl Cells {
+    pub(crate) fn get(&self, offset: isize) -> Option<u8> {
+        *self.values.get(&(self.pointer + offset)).unwrap_or(&self.default)
+    }
+
+    pub(crate) fn set(&mut self, offset: isize, value: Option<u8>) {
+        self.values.insert(self.pointer + offset, value);
+    }
+
+    /// Forgets everything but the current cell, which is 0 after every loop
+    pub(crate) fn forget(&mut self) {
+        self.pointer = 0;
+        self.values = HashMap::from([(0, Some(0))]);
+        self.default = None;
+    }
+}
+
+/// What one iteration of a simple loop does to a cell
+#[derive (Clone, Copy)]
+enum Effect {
+    Add(u8),
+    Set(u8),
+}
+
+/// Returns the number of bytes the program outputs if it runs to completion
+/// 
+/// Straight-line code is followed while tracking the known cell values. Loops that
+/// output, including through `Cat` or `PrintPointer`, must be simple counted loops
+/// without nested loops, input or pointer drift, entered on a known cell, otherwise
+/// the count can't be known and `None` is returned.
+pub(crate) fn output_len(instructions: &[Instruction], jump_table: &[usize], zeroed_tape: bool) -> Option<usize> {
+    let mut cells = Cells { pointer: 0, values: HashMap::new(), default: zeroed_tape.then_some(0) };
+    let mut count: usize = 0;
+    let mut i = 0;
+
+    while i < instructions.len() {
+        match instructions[i] {
+            Instruction::OpenLoop => {
+                let end = jump_table[i];
+                let body = &instructions[i + 1..end];
+                let outputs = body.iter().filter(|&&v| v == Instruction::Output).count();
+                let prints = body.iter().any(|v| matches!(v, Instruction::Output | Instruction::Cat | Instruction::PrintPointer));
+
+                let iterations = match cells.get(0) {
+                    Some(0) => Some(0),
+                    Some(value) => counted_loop(body, value, &mut cells),
+                    None => None,
+                };
+
+                // Loops that don't output can run any number of times, the cells they
+                // touch are just no longer known
+                match (iterations, prints) {
+                    (Some(n), _) => count = count.checked_add(n.checked_mul(outputs)?)?,
+                    (None, false) => cells.forget(),
+                    (None, true) => return None,
+                }
+                i = end;
+            },
+            Instruction::CloseLoop => (),
+            Instruction::PointerInc => cells.pointer += 1,
+            Instruction::PointerDec => cells.pointer -= 1,
+            Instruction::MovePointer(n) => cells.pointer += n,
+            Instruction::ByteInc => cells.set(0, cells.get(0).map(|v| v.wrapping_add(1))),
+            Instruction::ByteDec => cells.set(0, cells.get(0).map(|v| v.wrapping_sub(1))),
+            Instruction::AddByte(n) => cells.set(0, cells.get(0).map(|v| v.wrapping_add(n))),
+            Instruction::Clear => cells.set(0, Some(0)),
+            Instruction::SetByte(n) => cells.set(0, Some(n)),
+            Instruction::Input | Instruction::PeekInput | Instruction::Random => cells.set(0, None),
+            Instruction::MulAdd { offset, factor } => {
+                let value = cells.get(0).zip(cells.get(offset)).map(|(v, t)| t.wrapping_add(v.wrapping_mul(factor)));
+                cells.set(offset, value);
+            },
+            Instruction::Scan(_) => cells.forget(),
+            // Like the `[.,]` loop it replaces, it's skipped on a cell known to be 0
+            Instruction::Cat if cells.get(0) == Some(0) => (),
+            // The number of digits depends on where the pointer is on the tape
+            Instruction::Cat | Instruction::PrintPointer => return None,
+            Instruction::Output => count = count.checked_add(1)?,
+            Instruction::DumpTape | Instruction::Signal => (),
+        }
+
+        i += 1;
+    }
+
+    Some(count)
+}
+
+/// Applies a counted loop entered on `value` to the cells and returns its number of iterations
+fn counted_loop(body: &[Instruction], value: u8, cells: &mut Cells) -> Option<usize> {
+    let mut offset: isize = 0;
+    let mut effects: HashMap<isize, Effect> = HashMap::new();
+
+    for &instruction in body {
+        let add = match instruction {
+            Instruction::PointerInc => { offset += 1; continue },
+            Instruction::PointerDec => { offset -= 1; continue },
+            Instruction::MovePointer(n) => { offset += n; continue },
+            Instruction::Output | Instruction::DumpTape | Instruction::Signal => continue,
+            Instruction::ByteInc => Effect::Add(1),
+            Instruction::ByteDec => Effect::Add(u8::MAX),
+            Instruction::AddByte(n) => Effect::Add(n),
+            Instruction::Clear => Effect::Set(0),
+            Instruction::SetByte(n) => Effect::Set(n),
+            _ => return None,
+        };
+
+        let effect = match (effects.get(&offset), add) {
+            (_, Effect::Set(v)) => Effect::Set(v),
+            (Some(&Effect::Add(a)), Effect::Add(b)) => Effect::Add(a.wrapping_add(b)),
+            (Some(&Effect::Set(a)), Effect::Add(b)) => Effect::Set(a.wrapping_add(b)),
+            (None, effect) => effect,
+        };
+        effects.insert(offset, effect);
+    }
+
+    let step = match (offset, effects.get(&0)) {
+        (0, Some(&Effect::Add(step))) => step,
+        _ => return None,
+    };
+    // Loops that never bring their cell to 0 don't terminate
+    let iterations = (1..=256usize).find(|&n| value.wrapping_add(step.wrapping_mul(n as u8)) == 0)?;
+
+    for (&at, &effect) in &effects {
+        let new = match effect {
+            Effect::Add(delta) => cells.get(at).map(|v| v.wrapping_add(delta.wrapping_mul(iterations as u8))),
+            Effect::Set(v) => Some(v),
+        };
+        cells.set(at, new);
+    }
+    cells.set(0, Some(0));
+
+    Some(iterations)
+}
+
+/// Returns the offsets from the starting cell of every cell the program may touch
+/// 
+/// The pointer only moves a fixed amount through straight-line code, so every loop
+/// body must leave it where it found it, otherwise how far it goes depends on the
+/// cells and `None` is returned. Loops that may be skipped still count.
+pub(crate) fn reachable_cells(instructions: &[Instruction]) -> Option<RangeInclusive<isize>> {
+    let mut offset: isize = 0;
+    let (mut min, mut max) = (0, 0);
+    let mut loops = Vec::new();
+
+    for &instruction in instructions {
+        match instruction {
+            Instruction::PointerInc => offset += 1,
+            Instruction::PointerDec => offset -= 1,
+            Instruction::MovePointer(n) => offset += n,
+            Instruction::MulAdd { offset: target, .. } => {
+                min = min.min(offset + target);
+                max = max.max(offset + target);
+            },
+            Instruction::OpenLoop => loops.push(offset),
+            Instruction::CloseLoop => match loops.pop() {
+                Some(start) if start == offset => (),
+                _ => return None,
+            },
+            Instruction::Scan(_) => return None,
+            _ => (),
+        }
+
+        min = min.min(offset);
+        max = max.max(offset);
+    }
+
+    Some(min..=max)
+}
@@ -0,0 +1,183 @@
+//! Compact binary serialization of parsed programs
+//!
+//! The format is versioned so bytecode written by an older or newer build is
+//! rejected instead of being misread:
+//!
+//! | Offset | Size | Content                                  |
+//! |--------|------|------------------------------------------|
+//! | 0      | 4    | Magic bytes `BFBC`                       |
+//! | 4      | 1    | Format version (currently `2`)           |
+//! | 5      | 4    | Instruction count (`u32`, little endian) |
+//! | 9      | n    | Instructions                             |
+//!
+//! Every instruction is an opcode byte followed by its operands, all little endian:
+//!
+//! | Opcode | Instruction                                       | Operands                  |
+//! |--------|---------------------------------------------------|---------------------------|
+//! | 0 - 7  | `PointerInc` to `CloseLoop`, in declaration order | none                      |
+//! | 8      | `AddByte`                                         | `u8` value                |
+//! | 9      | `MovePointer`                                     | `i64` offset              |
+//! | 10     | `Clear`                                           | none                      |
+//! | 11     | `Scan`                                            | `i64` step                |
+//! | 12     | `MulAdd`                                          | `i64` offset, `u8` factor |
+//! | 13     | `Random`                                          | none                      |
+//! | 14     | `Cat`                                             | none                      |
+//! | 15     | `SetByte`                                         | `u8` value                |
+//! | 16     | `DumpTape`                                        | none                      |
+//! | 17     | `Signal`                                          | none                      |
+//! | 18     | `PrintPointer`                                    | none                      |
+//! | 19     | `PeekInput`                                       | none                      |
+//!
+//! Offsets larger than `MAX_BYTECODE_OFFSET` in either direction are rejected. The
+//! jump table isn't stored, it's rebuilt from the brackets when the program is loaded.
+
+use crate::{BFError, Instruction, MAX_BYTECODE_OFFSET};
+
+const MAGIC: &[u8; 4] = b"BFBC";
+const VERSION: u8 = 2;
+const HEADER_LEN: usize = 9;
+
+fn write_instruction(bytes: &mut Vec<u8>, instruction: Instruction) {
+    match instruction {
+        Instruction::PointerInc => bytes.push(0),
+        Instruction::PointerDec => bytes.push(1),
+        Instruction::ByteInc => bytes.push(2),
+        Instruction::ByteDec => bytes.push(3),
+        Instruction::Output => bytes.push(4),
+        Instruction::Input => bytes.push(5),
+        Instruction::OpenLoop => bytes.push(6),
+        Instruction::CloseLoop => bytes.push(7),
+        Instruction::AddByte(n) => bytes.extend([8, n]),
+        Instruction::MovePointer(n) => {
+            bytes.push(9);
+            bytes.extend((n as i64).to_le_bytes());
+        },
+        Instruction::Clear => bytes.push(10),
+        Instruction::Scan(n) => {
+            bytes.push(11);
+            bytes.extend((n as i64).to_le_bytes());
+        },
+        Instruction::MulAdd { offset, factor } => {
+            bytes.push(12);
+            bytes.extend((offset as i64).to_le_bytes());
+            bytes.push(factor);
+        },
+        Instruction::Random => bytes.push(13),
+        Instruction::Cat => bytes.push(14),
+        Instruction::SetByte(n) => bytes.extend([15, n]),
+        Instruction::DumpTape => bytes.push(16),
+        Instruction::Signal => bytes.push(17),
+        Instruction::PrintPointer => bytes.push(18),
+        Instruction::PeekInput => bytes.push(19),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BFError> {
+        let chunk = self.bytes
+            .get(self.offset..self.offset + N)
+            .ok_or(BFError::TruncatedBytecode)?;
+        self.offset += N;
+
+        Ok(chunk.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, BFError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    /// Reads a pointer offset, rejecting the ones past `MAX_BYTECODE_OFFSET`
+    fn pointer_offset(&mut self) -> Result<isize, BFError> {
+        let offset = self.offset;
+        let value = i64::from_le_bytes(self.take()?);
+
+        match value.unsigned_abs() > MAX_BYTECODE_OFFSET as u64 {
+            true => Err(BFError::BytecodeOffsetOutOfRange { value, offset }),
+            false => Ok(value as isize),
+        }
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, BFError> {
+        let offset = self.offset;
+
+        match self.u8()? {
+            0 => Ok(Instruction::PointerInc),
+            1 => Ok(Instruction::PointerDec),
+            2 => Ok(Instruction::ByteInc),
+            3 => Ok(Instruction::ByteDec),
+            4 => Ok(Instruction::Output),
+            5 => Ok(Instruction::Input),
+            6 => Ok(Instruction::OpenLoop),
+            7 => Ok(Instruction::CloseLoop),
+            8 => Ok(Instruction::AddByte(self.u8()?)),
+            9 => Ok(Instruction::MovePointer(self.pointer_offset()?)),
+            10 => Ok(Instruction::Clear),
+            11 => Ok(Instruction::Scan(self.pointer_offset()?)),
+            12 => Ok(Instruction::MulAdd { offset: self.pointer_offset()?, factor: self.u8()? }),
+            13 => Ok(Instruction::Random),
+            14 => Ok(Instruction::Cat),
+            15 => Ok(Instruction::SetByte(self.u8()?)),
+            16 => Ok(Instruction::DumpTape),
+            17 => Ok(Instruction::Signal),
+            18 => Ok(Instruction::PrintPointer),
+            19 => Ok(Instruction::PeekInput),
+            opcode => Err(BFError::InvalidOpcode { opcode, offset }),
+        }
+    }
+}
+
+pub(crate) fn to_bytecode(instructions: &[Instruction]) -> Result<Vec<u8>, BFError> {
+    let count = u32::try_from(instructions.len()).map_err(|_| BFError::ProgramTooLarge {
+        count: instructions.len(),
+        max: u32::MAX as usize,
+    })?;
+    let mut bytes = Vec::with_capacity(HEADER_LEN + instructions.len());
+
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&count.to_le_bytes());
+    for &instruction in instructions {
+        write_instruction(&mut bytes, instruction);
+    }
+
+    Ok(bytes)
+}
+
+/// Loads a program previously serialized with `BFInterpreter::to_bytecode`
+///
+/// # Examples
+/// ```ignore
+/// let mut interpreter = BFInterpreter::new(None)?;
+/// interpreter.run("+++>+++<[>.<-]");
+///
+/// let bytecode = interpreter.to_bytecode()?;
+/// let instructions = from_bytecode(&bytecode)?;
+/// interpreter.run_compiled(&instructions)?;
+/// ```
+pub fn from_bytecode(bytes: &[u8]) -> Result<Vec<Instruction>, BFError> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+        return Err(BFError::InvalidBytecodeHeader);
+    }
+
+    if bytes[4] != VERSION {
+        return Err(BFError::UnsupportedBytecodeVersion(bytes[4]));
+    }
+
+    let count = u32::from_le_bytes([bytes[5], bytes[6], bytes[7], bytes[8]]) as usize;
+    let mut reader = Reader { bytes, offset: HEADER_LEN };
+
+    let instructions = (0..count)
+        .map(|_| reader.instruction())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if reader.offset != bytes.len() {
+        return Err(BFError::InvalidBytecodeLength { expected: reader.offset, found: bytes.len() });
+    }
+
+    Ok(instructions)
+}
@@ -0,0 +1,106 @@
+//! Bundles of resource limits and permissions for running untrusted programs
+
+use std::time::Duration;
+
+use crate::{BFInterpreterConfig, DEFAULT_TAPE_SIZE};
+
+/// Resource limits and permissions applied together to a config
+///
+/// `Capabilities::sandboxed` is a vetted preset for running arbitrary user code.
+/// Every limit set here replaces the one in the config it's applied to, except the
+/// tape size, which is only lowered.
+///
+/// # Examples
+/// ```ignore
+/// let config = Capabilities::sandboxed().apply(BFInterpreterConfig::default());
+/// let mut interpreter = BFInterpreter::new(Some(config))?;
+/// let output = interpreter.try_run(untrusted_source)?;
+/// ```
+#[derive (Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    /// See `BFInterpreterConfig::max_steps`
+    pub max_steps: Option<u64>,
+    /// See `BFInterpreterConfig::max_duration`
+    pub max_duration: Option<Duration>,
+    /// See `BFInterpreterConfig::max_output`
+    pub max_output: Option<usize>,
+    /// Caps both `tape_size` and `max_tape_size`
+    pub max_tape_size: Option<usize>,
+    /// See `BFInterpreterConfig::max_source_len`
+    pub max_source_len: Option<usize>,
+    /// See `BFInterpreterConfig::allow_input`, also turns `block_on_input` off when disabled
+    pub allow_input: bool,
+    /// Lets the program reach the host beyond its output
+    ///
+    /// When disabled `Signal` and `DumpTape` fail, and `echo_output` and `crash_output`
+    /// are turned off
+    pub allow_side_effects: bool,
+}
+
+impl Capabilities {
+    /// No limits and every permission, what a default config allows
+    pub fn unrestricted() -> Self {
+        Self {
+            max_steps: None,
+            max_duration: None,
+            max_output: None,
+            max_tape_size: None,
+            max_source_len: None,
+            allow_input: true,
+            allow_side_effects: true,
+        }
+    }
+
+    /// Limits for running arbitrary user code
+    ///
+    /// | Restriction      | Value                                 |
+    /// |------------------|---------------------------------------|
+    /// | `max_steps`      | 10 000 000 instructions               |
+    /// | `max_duration`   | 1 second, checked every few thousand steps |
+    /// | `max_output`     | 1 MiB                                 |
+    /// | `max_tape_size`  | 65 536 cells                          |
+    /// | `max_source_len` | 1 MiB                                 |
+    /// | input            | disabled, `,` fails                   |
+    /// | side effects     | disabled, no stdout, stderr or files  |
+    ///
+    /// Output is still collected and returned by `try_run`. Callbacks registered on the
+    /// interpreter and `start_recording` are up to the embedder and aren't restricted.
+    pub fn sandboxed() -> Self {
+        Self {
+            max_steps: Some(10_000_000),
+            max_duration: Some(Duration::from_secs(1)),
+            max_output: Some(1 << 20),
+            max_tape_size: Some(65_536),
+            max_source_len: Some(1 << 20),
+            allow_input: false,
+            allow_side_effects: false,
+        }
+    }
+
+    /// Returns the config with these limits and permissions applied
+    pub fn apply(&self, config: BFInterpreterConfig) -> BFInterpreterConfig {
+        let mut config = BFInterpreterConfig {
+            max_steps: self.max_steps,
+            max_duration: self.max_duration,
+            max_output: self.max_output,
+            max_source_len: self.max_source_len,
+            allow_input: self.allow_input,
+            allow_side_effects: self.allow_side_effects,
+            ..config
+        };
+
+        if let Some(max) = self.max_tape_size {
+            config.tape_size = Some(config.tape_size.unwrap_or(DEFAULT_TAPE_SIZE).min(max));
+            config.max_tape_size = Some(config.max_tape_size.map_or(max, |v| v.min(max)));
+        }
+        if !self.allow_input {
+            config.block_on_input = false;
+        }
+        if !self.allow_side_effects {
+            config.echo_output = false;
+            config.crash_output = None;
+        }
+
+        config
+    }
+}
@@ -0,0 +1,116 @@
+//! Control flow graph of a parsed program
+//!
+//! Built from the jump table, so collapsed loops like `[-]` are single
+//! instructions inside a block rather than loops of their own.
+
+use std::fmt::Write;
+
+use crate::Instruction;
+
+/// A run of instructions that always execute in order, from `start` to `end` exclusive
+/// 
+/// Blocks end on a bracket, since that's where control can branch
+#[derive (Clone, Copy, Debug, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Why control can flow from one block to another
+#[derive (Clone, Copy, Debug, PartialEq)]
+pub enum EdgeKind {
+    /// Falls through to the next block, including entering or leaving a loop
+    Next,
+    /// Jumps from a `[` on a zero cell past its `]`
+    Skip,
+    /// Jumps from a `]` on a non-zero cell back to the start of its loop body
+    Back,
+}
+
+/// Edge between two blocks, `from` and `to` are indices into `Cfg::blocks`
+#[derive (Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// Basic blocks of a program and the edges between them
+/// 
+/// Edges that leave the program, like skipping a loop at its very end, are left out
+#[derive (Clone, Debug, Default, PartialEq)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+impl Cfg {
+    pub(crate) fn new(instructions: &[Instruction], jump_table: &[usize]) -> Self {
+        let mut blocks = Vec::new();
+        let mut start = 0;
+
+        for (i, instruction) in instructions.iter().enumerate() {
+            if matches!(instruction, Instruction::OpenLoop | Instruction::CloseLoop) {
+                blocks.push(BasicBlock { start, end: i + 1 });
+                start = i + 1;
+            }
+        }
+        if start < instructions.len() {
+            blocks.push(BasicBlock { start, end: instructions.len() });
+        }
+
+        // Every jump lands right after a bracket, which is always the start of a block
+        let block_at = |index: usize| blocks.binary_search_by_key(&index, |b| b.start).ok();
+        let mut edges = Vec::new();
+
+        for (from, block) in blocks.iter().enumerate() {
+            let last = block.end - 1;
+            let mut add = |to: Option<usize>, kind| {
+                if let Some(to) = to {
+                    edges.push(Edge { from, to, kind });
+                }
+            };
+
+            match instructions[last] {
+                Instruction::OpenLoop => {
+                    add(block_at(block.end), EdgeKind::Next);
+                    add(block_at(jump_table[last] + 1), EdgeKind::Skip);
+                },
+                Instruction::CloseLoop => {
+                    add(block_at(jump_table[last] + 1), EdgeKind::Back);
+                    add(block_at(block.end), EdgeKind::Next);
+                },
+                _ => add(block_at(block.end), EdgeKind::Next),
+            }
+        }
+
+        Self { blocks, edges }
+    }
+
+    /// Renders the graph in graphviz dot format, with the source of every block as its label
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let dot = interpreter.control_flow_graph().to_dot(interpreter.instructions());
+    /// std::fs::write("program.dot", dot)?;
+    /// ```
+    pub fn to_dot(&self, instructions: &[Instruction]) -> String {
+        let mut dot = String::from("digraph cfg {\n    node [shape=box];\n");
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            let source: String = instructions[block.start..block.end].iter().map(|v| v.to_string()).collect();
+            let _ = writeln!(dot, "    b{} [label=\"{}: {}\"];", i, block.start, source);
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Next => "solid",
+                EdgeKind::Skip => "dashed",
+                EdgeKind::Back => "bold",
+            };
+            let _ = writeln!(dot, "    b{} -> b{} [style={}];", edge.from, edge.to, style);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,106 @@
+//! Dialects defined in text files, the runtime counterpart of `bf_dialect!`
+//!
+//! Every line maps a char to an instruction by name, `#` starts a comment:
+//!
+//! ```text
+//! # WASD
+//! D = PointerInc
+//! A = PointerDec
+//! '#' = Output
+//! ```
+//!
+//! Chars can be quoted to map `#`, `=` or whitespace, and quoted chars accept the
+//! `\n`, `\t`, `\\` and `\'` escapes, e.g. `'\n' = Output`.
+
+use std::collections::HashMap;
+
+use crate::{BFError, Instruction};
+
+/// Instructions every dialect must map
+const STANDARD_INSTRUCTIONS: [Instruction; 8] = [
+    Instruction::PointerInc,
+    Instruction::PointerDec,
+    Instruction::ByteInc,
+    Instruction::ByteDec,
+    Instruction::Output,
+    Instruction::Input,
+    Instruction::OpenLoop,
+    Instruction::CloseLoop,
+];
+
+/// Parses a dialect definition into a `custom_instructions` map
+/// 
+/// Fails if a line is malformed or names an unknown instruction, if a char is mapped
+/// twice, or if one of the eight standard instructions isn't mapped. Extensions like
+/// `Random` or `PrintPointer` can be mapped too.
+pub fn parse_dialect(definition: &str) -> Result<HashMap<char, Instruction>, BFError> {
+    let mut map = HashMap::new();
+
+    for (i, line) in definition.lines().enumerate() {
+        let invalid = |reason: &str| BFError::InvalidDialect { line: i + 1, reason: reason.to_string() };
+
+        let (c, rest) = match line.trim_start().strip_prefix('\'') {
+            Some(quoted) => {
+                let mut chars = quoted.chars();
+                let c = match chars.next().ok_or_else(|| invalid("missing char"))? {
+                    '\\' => match chars.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some(c @ ('\\' | '\'')) => c,
+                        _ => return Err(invalid("unknown escape")),
+                    },
+                    c => c,
+                };
+                let rest = chars.as_str().strip_prefix('\'').ok_or_else(|| invalid("unclosed quote"))?;
+                (c, rest)
+            },
+            None => {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let mut chars = line.chars();
+                let c = chars.next().ok_or_else(|| invalid("missing char"))?;
+                (c, chars.as_str())
+            },
+        };
+
+        let name = rest.trim_start()
+            .strip_prefix('=')
+            .ok_or_else(|| invalid("expected `=` after the char"))?;
+        let name = name.split('#').next().unwrap_or_default().trim();
+
+        let instruction = match name {
+            "PointerInc" => Instruction::PointerInc,
+            "PointerDec" => Instruction::PointerDec,
+            "ByteInc" => Instruction::ByteInc,
+            "ByteDec" => Instruction::ByteDec,
+            "Output" => Instruction::Output,
+            "Input" => Instruction::Input,
+            "OpenLoop" => Instruction::OpenLoop,
+            "CloseLoop" => Instruction::CloseLoop,
+            "Random" => Instruction::Random,
+            "DumpTape" => Instruction::DumpTape,
+            "Signal" => Instruction::Signal,
+            "PrintPointer" => Instruction::PrintPointer,
+            "PeekInput" => Instruction::PeekInput,
+            _ => return Err(invalid(&format!("unknown instruction `{}`", name))),
+        };
+
+        if map.insert(c, instruction).is_some() {
+            return Err(BFError::DuplicateDialectChar(c));
+        }
+    }
+
+    match STANDARD_INSTRUCTIONS.into_iter().find(|i| !map.values().any(|v| v == i)) {
+        Some(missing) => Err(BFError::MissingDialectInstruction(missing)),
+        None => Ok(map),
+    }
+}
+
+/// Reads and parses a dialect definition file, see `parse_dialect`
+pub fn load_dialect<P: AsRef<std::path::Path>>(path: P) -> Result<HashMap<char, Instruction>, BFError> {
+    let definition = std::fs::read_to_string(path).map_err(|e| BFError::Io(e.to_string()))?;
+    parse_dialect(&definition)
+}
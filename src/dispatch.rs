@@ -0,0 +1,102 @@
+//! Instruction dispatch through a table of function pointers
+//!
+//! The default dispatch is a `match` on every step. With `Dispatch::Table` the
+//! handler of every instruction is looked up once when the program is loaded, and
+//! each step is a single indirect call. Both run the same handlers, so they always
+//! produce the same results, the table only exists to compare their speed.
+
+use crate::{BFError, BFInterpreter, Instruction};
+
+/// How `step` picks the code that runs an instruction
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum Dispatch {
+    /// Matches on the instruction every step
+    #[default]
+    Match,
+    /// Calls a function pointer resolved when the program is loaded
+    Table,
+}
+
+/// Runs one instruction, which is passed in to read its operands
+pub(crate) type Handler = fn(&mut BFInterpreter, Instruction) -> Result<(), BFError>;
+
+/// Resolves the handler of every instruction of the program
+pub(crate) fn handlers(instructions: &[Instruction]) -> Vec<Handler> {
+    instructions.iter().map(|&instruction| handler(instruction)).collect()
+}
+
+fn handler(instruction: Instruction) -> Handler {
+    match instruction {
+        Instruction::PointerInc => |i, _| i.pointer_inc(),
+        Instruction::PointerDec => |i, _| i.pointer_dec(),
+        Instruction::ByteInc => |i, _| {
+            i.byte_inc();
+            Ok(())
+        },
+        Instruction::ByteDec => |i, _| {
+            i.byte_dec();
+            Ok(())
+        },
+        Instruction::Output => |i, _| i.output(),
+        Instruction::Input => |i, _| i.input(),
+        Instruction::OpenLoop | Instruction::CloseLoop => |i, _| {
+            i.jump();
+            Ok(())
+        },
+        Instruction::Random => |i, _| {
+            i.data[i.data_pointer] = i.rng.next_u8();
+            Ok(())
+        },
+        Instruction::DumpTape => |i, _| i.dump_tape(),
+        Instruction::Signal => |i, _| i.signal(),
+        Instruction::PrintPointer => |i, _| i.print_pointer(),
+        Instruction::PeekInput => |i, _| i.peek_input(),
+        Instruction::AddByte(_) => add_byte,
+        Instruction::MovePointer(_) => move_pointer,
+        Instruction::Clear => |i, _| {
+            i.data[i.data_pointer] = 0;
+            Ok(())
+        },
+        Instruction::Scan(_) => scan,
+        Instruction::MulAdd { .. } => mul_add,
+        Instruction::Cat => |i, _| i.cat(),
+        Instruction::SetByte(_) => set_byte,
+    }
+}
+
+fn add_byte(interpreter: &mut BFInterpreter, instruction: Instruction) -> Result<(), BFError> {
+    if let Instruction::AddByte(n) = instruction {
+        interpreter.add_byte(n);
+    }
+
+    Ok(())
+}
+
+fn move_pointer(interpreter: &mut BFInterpreter, instruction: Instruction) -> Result<(), BFError> {
+    match instruction {
+        Instruction::MovePointer(n) => interpreter.move_pointer(n),
+        _ => Ok(()),
+    }
+}
+
+fn scan(interpreter: &mut BFInterpreter, instruction: Instruction) -> Result<(), BFError> {
+    match instruction {
+        Instruction::Scan(n) => interpreter.scan(n),
+        _ => Ok(()),
+    }
+}
+
+fn mul_add(interpreter: &mut BFInterpreter, instruction: Instruction) -> Result<(), BFError> {
+    match instruction {
+        Instruction::MulAdd { offset, factor } => interpreter.mul_add(offset, factor),
+        _ => Ok(()),
+    }
+}
+
+fn set_byte(interpreter: &mut BFInterpreter, instruction: Instruction) -> Result<(), BFError> {
+    if let Instruction::SetByte(n) = instruction {
+        interpreter.data[interpreter.data_pointer] = n;
+    }
+
+    Ok(())
+}
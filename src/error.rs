@@ -0,0 +1,100 @@
+use std::{fmt, time::Duration};
+
+use crate::Instruction;
+
+/// Errors reported by the interpreter
+#[derive (Clone, Debug, PartialEq)]
+pub enum BFError {
+    /// The line comment char is also mapped to an instruction
+    LineCommentCollision(char),
+    /// The source contains a char that isn't mapped to any instruction
+    InvalidInstruction { instruction: char, index: usize },
+    /// A loop is closed without being opened, or opened without being closed
+    UnbalancedBrackets,
+    /// Reading the source or writing the output failed
+    Io(String),
+    /// The data pointer was moved outside of the tape by the instruction at `index`
+    PointerOutOfBounds { pointer: isize, index: usize },
+    /// A growable tape would have grown past `max_tape_size` cells
+    TapeLimitExceeded(usize),
+    /// Reading stdin failed on every attempt
+    InputFailed(usize),
+    /// `,` ran with no input set and `on_missing_input` set to `OnMissingInput::Error`
+    NoInput(usize),
+    /// The program ran for the maximum number of steps without halting
+    StepLimitExceeded(u64),
+    /// The program printed more than `max_output` bytes
+    OutputLimitExceeded(usize),
+    /// `,` ran with `allow_input` disabled
+    InputDisabled(usize),
+    /// An instruction with a host side effect ran with `allow_side_effects` disabled
+    SideEffectDisabled { instruction: Instruction, index: usize },
+    /// The program ran for longer than `max_duration` without halting
+    Timeout(Duration),
+    /// The configured tape size is 0
+    EmptyTape,
+    /// The configured initial pointer is outside of the tape
+    InvalidInitialPointer { pointer: usize, tape_size: usize },
+    /// Resizing the tape would leave the data pointer outside of it
+    TapeResizeOutOfBounds { pointer: usize, size: usize },
+    /// The program has more instructions than allowed by `validate_size` or the bytecode format
+    ProgramTooLarge { count: usize, max: usize },
+    /// The source is longer than `max_source_len` bytes
+    /// 
+    /// Sources given as readers stop being read past the limit, so `len` is `max + 1`
+    SourceTooLong { len: usize, max: usize },
+    /// A line of a dialect definition is malformed or names an unknown instruction
+    InvalidDialect { line: usize, reason: String },
+    /// A dialect definition maps the same char twice
+    DuplicateDialectChar(char),
+    /// A dialect definition doesn't map one of the eight standard instructions
+    MissingDialectInstruction(Instruction),
+    /// The bytecode does not start with the `BFBC` magic header
+    InvalidBytecodeHeader,
+    /// The bytecode was written by a different version of the format
+    UnsupportedBytecodeVersion(u8),
+    /// The bytecode ended before all of its instructions were read
+    TruncatedBytecode,
+    /// The bytecode has trailing bytes after its last instruction
+    InvalidBytecodeLength { expected: usize, found: usize },
+    /// The bytecode contains an opcode that doesn't map to any instruction
+    InvalidOpcode { opcode: u8, offset: usize },
+    /// The bytecode contains a pointer offset larger than `MAX_BYTECODE_OFFSET`
+    BytecodeOffsetOutOfRange { value: i64, offset: usize },
+}
+
+impl fmt::Display for BFError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BFError::LineCommentCollision(c) => write!(f, "Line comment char ({}) is also mapped to an instruction", c),
+            BFError::InvalidInstruction { instruction, index } => write!(f, "Invalid instruction ({}) at index {}", instruction, index),
+            BFError::UnbalancedBrackets => write!(f, "Unbalanced brackets"),
+            BFError::Io(e) => write!(f, "IO error: {}", e),
+            BFError::PointerOutOfBounds { pointer, index } => write!(f, "Out of bounds trying to move pointer to {} at index {}", pointer, index),
+            BFError::TapeLimitExceeded(n) => write!(f, "Tape limit of {} cells exceeded", n),
+            BFError::InputFailed(n) => write!(f, "Reading input failed after {} attempts", n),
+            BFError::NoInput(i) => write!(f, "Instruction {} reads input but no input was set", i),
+            BFError::StepLimitExceeded(n) => write!(f, "Step limit exceeded after {} steps", n),
+            BFError::Timeout(d) => write!(f, "Timed out after {:?}", d),
+            BFError::OutputLimitExceeded(n) => write!(f, "Output limit of {} bytes exceeded", n),
+            BFError::InputDisabled(index) => write!(f, "Input is disabled but read at index {}", index),
+            BFError::SideEffectDisabled { instruction, index } => write!(f, "Side effects are disabled but {:?} ran at index {}", instruction, index),
+            BFError::EmptyTape => write!(f, "The tape must have at least 1 cell"),
+            BFError::InvalidInitialPointer { pointer, tape_size } => write!(f, "Initial pointer {} is outside of the tape of size {}", pointer, tape_size),
+            BFError::TapeResizeOutOfBounds { pointer, size } => write!(f, "Can't resize the tape to {} cells, the pointer is on cell {}", size, pointer),
+            BFError::InvalidDialect { line, reason } => write!(f, "Invalid dialect at line {}: {}", line, reason),
+            BFError::DuplicateDialectChar(c) => write!(f, "Dialect maps the char ({}) more than once", c),
+            BFError::MissingDialectInstruction(i) => write!(f, "Dialect doesn't map the {:?} instruction", i),
+            BFError::ProgramTooLarge { count, max } => write!(f, "Program has {} instructions, the maximum is {}", count, max),
+            BFError::SourceTooLong { len, max } => write!(f, "Source is {} bytes long, the maximum is {}", len, max),
+            BFError::InvalidBytecodeHeader => write!(f, "Invalid bytecode header"),
+            BFError::UnsupportedBytecodeVersion(v) => write!(f, "Unsupported bytecode version {}", v),
+            BFError::TruncatedBytecode => write!(f, "Bytecode ended unexpectedly"),
+            BFError::InvalidBytecodeLength { expected, found } => write!(f, "Expected {} bytes of bytecode, found {}", expected, found),
+            BFError::InvalidOpcode { opcode, offset } => write!(f, "Invalid opcode ({}) at offset {}", opcode, offset),
+            BFError::BytecodeOffsetOutOfRange { value, offset } => write!(f, "Pointer offset {} at offset {} is out of range", value, offset),
+        }
+    }
+}
+
+impl std::error::Error for BFError {}
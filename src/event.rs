@@ -0,0 +1,15 @@
+use crate::Instruction;
+
+/// Something that happened while running a program
+/// 
+/// Indices refer to the parsed instructions, after optimization
+#[derive (Clone, Copy, Debug, PartialEq)]
+pub enum ExecEvent {
+    /// The instruction at `index` ran, leaving the data pointer on `data_pointer`
+    /// holding `cell`
+    Step { index: usize, instruction: Instruction, data_pointer: usize, cell: u8 },
+    /// The instruction at `index` printed `value`
+    Output { index: usize, value: u8 },
+    /// The instruction at `index` read `value`, or hit the end of the input
+    Input { index: usize, value: Option<u8> },
+}
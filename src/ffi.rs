@@ -0,0 +1,108 @@
+//! C interface to the interpreter
+//!
+//! Only available with the `ffi` feature, see `include/brainfuck.h` for the C
+//! declarations. Interpreters are opaque pointers created by `bf_interpreter_new`
+//! and owned by the caller until passed to `bf_interpreter_free`. Everything else
+//! only borrows the pointers it's given for the duration of the call.
+
+use std::{ffi::{c_char, c_int, CStr}, panic::{self, AssertUnwindSafe}, ptr};
+
+use crate::{BFInterpreter, BFInterpreterConfig};
+
+/// The program ran to completion
+pub const BF_OK: c_int = 0;
+/// The program failed, e.g. on unbalanced brackets or a pointer out of the tape
+pub const BF_ERROR: c_int = 1;
+/// A pointer argument was null or the source wasn't valid UTF-8
+pub const BF_INVALID_ARGUMENT: c_int = 2;
+/// The interpreter panicked, it shouldn't be used anymore except to free it
+pub const BF_PANIC: c_int = 3;
+
+/// Creates an interpreter with the default config, or returns null if that fails
+/// 
+/// `,` reads 0 once the input given to `bf_interpreter_run` is exhausted
+#[no_mangle]
+pub extern "C" fn bf_interpreter_new() -> *mut BFInterpreter {
+    let config = BFInterpreterConfig {
+        eof_behavior: crate::EofBehavior::Zero,
+        ..Default::default()
+    };
+
+    match panic::catch_unwind(|| BFInterpreter::new(Some(config))) {
+        Ok(Ok(interpreter)) => Box::into_raw(Box::new(interpreter)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Runs the nul-terminated program with `input_len` bytes of input and returns a `BF_*` code
+/// 
+/// The output is kept in the interpreter, read it with `bf_interpreter_output`. It
+/// holds what the program printed before failing on errors.
+/// 
+/// # Safety
+/// `interpreter` must come from `bf_interpreter_new` and not be freed, `source` must be
+/// a nul-terminated string, and `input` must point to `input_len` readable bytes. It
+/// may be null when `input_len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn bf_interpreter_run(
+    interpreter: *mut BFInterpreter,
+    source: *const c_char,
+    input: *const u8,
+    input_len: usize,
+) -> c_int {
+    if interpreter.is_null() || source.is_null() || (input.is_null() && input_len > 0) {
+        return BF_INVALID_ARGUMENT;
+    }
+
+    let interpreter = &mut *interpreter;
+    let source = match CStr::from_ptr(source).to_str() {
+        Ok(v) => v,
+        Err(_) => return BF_INVALID_ARGUMENT,
+    };
+    let input = match input_len {
+        0 => &[],
+        _ => std::slice::from_raw_parts(input, input_len),
+    };
+
+    // Unwinding into C is undefined behavior
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        interpreter.set_input(input);
+        interpreter.run_bytes(source)
+    }));
+
+    match result {
+        Ok(Ok(_)) => BF_OK,
+        Ok(Err(_)) => BF_ERROR,
+        Err(_) => BF_PANIC,
+    }
+}
+
+/// Returns the output of the last run and writes its length to `len`
+/// 
+/// The bytes are owned by the interpreter and stay valid until the next run or until
+/// it's freed. They aren't nul-terminated. Returns null if either pointer is null.
+/// 
+/// # Safety
+/// `interpreter` must come from `bf_interpreter_new` and not be freed, and `len` must
+/// be a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn bf_interpreter_output(interpreter: *const BFInterpreter, len: *mut usize) -> *const u8 {
+    if interpreter.is_null() || len.is_null() {
+        return ptr::null();
+    }
+
+    let output = (*interpreter).output_bytes();
+    *len = output.len();
+    output.as_ptr()
+}
+
+/// Frees an interpreter, does nothing if it's null
+/// 
+/// # Safety
+/// `interpreter` must come from `bf_interpreter_new` and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn bf_interpreter_free(interpreter: *mut BFInterpreter) {
+    if !interpreter.is_null() {
+        drop(Box::from_raw(interpreter));
+    }
+}
@@ -0,0 +1,2335 @@
+use std::{io::{self, Write}, fmt, borrow::Cow, collections::{HashMap, HashSet, VecDeque}, time::{Duration, Instant}};
+
+mod analysis;
+mod bytecode;
+mod capabilities;
+mod cfg;
+mod dialect;
+mod dispatch;
+mod error;
+mod event;
+mod macros;
+mod lint;
+mod optimizer;
+mod parse;
+mod rng;
+mod session;
+mod shrink;
+mod tape;
+#[cfg(feature = "async")]
+mod run_async;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "tape-image")]
+mod tape_image;
+
+pub use bytecode::from_bytecode;
+pub use capabilities::Capabilities;
+pub use cfg::{BasicBlock, Cfg, Edge, EdgeKind};
+pub use dialect::{load_dialect, parse_dialect};
+pub use dispatch::Dispatch;
+pub use error::BFError;
+pub use event::ExecEvent;
+pub use lint::Warning;
+pub use optimizer::{expand, OptLevel, OptReport};
+pub use parse::max_nesting_depth;
+pub use session::Session;
+pub use shrink::shrink;
+
+/// Number of cells on the tape when the config doesn't set `tape_size`
+pub const DEFAULT_TAPE_SIZE: usize = 1024;
+
+/// Number of times reading stdin is attempted before `,` gives up
+const MAX_INPUT_ATTEMPTS: usize = 3;
+
+/// Number of bytes `run_from_reader` reads at a time
+const READ_CHUNK_SIZE: usize = 8192;
+
+/// Number of steps run between two checks of `max_duration`
+pub const TIMEOUT_CHECK_INTERVAL: u64 = 4096;
+
+/// Largest pointer offset `from_bytecode` accepts, in either direction
+/// 
+/// Far past any tape a program needs, but small enough that `source` can write the
+/// moves out
+pub const MAX_BYTECODE_OFFSET: isize = 1 << 24;
+
+#[derive (Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    PointerInc,
+    PointerDec,
+    ByteInc,
+    ByteDec,
+    Output,
+    Input,
+    OpenLoop,
+    CloseLoop,
+
+    // Extensions, only available when mapped in `custom_instructions`
+    /// Sets the current cell to a random byte, see `rng_seed`
+    Random,
+    /// Prints the non-zero cells of the tape with their indices to stderr
+    DumpTape,
+    /// Calls the callback registered with `on_signal`, without touching the tape
+    Signal,
+    /// Outputs the data pointer as decimal digits
+    /// 
+    /// A teaching extension, not standard brainfuck: it shows learners where the pointer
+    /// is in the middle of their program's output.
+    PrintPointer,
+    /// Writes the next input byte to the current cell without consuming it
+    /// 
+    /// Lets event-driven programs check for pending input: with none pending the cell
+    /// is set as `eof_behavior` says and stdin isn't prompted. The next `,` reads the
+    /// same byte.
+    PeekInput,
+
+    // Produced by the optimizer, see `OptLevel`
+    /// Adds the value to the current cell, wrapping around
+    AddByte(u8),
+    /// Moves the data pointer by the offset
+    MovePointer(isize),
+    /// Sets the current cell to 0
+    Clear,
+    /// Moves the data pointer by the step until it lands on a cell with value 0
+    Scan(isize),
+    /// Adds the current cell times the factor to the cell at the offset
+    MulAdd { offset: isize, factor: u8 },
+    /// Copies input to output until the current cell is 0, the `[.,]` loop of `,[.,]`
+    Cat,
+    /// Sets the current cell to the value, a clear loop followed by a run of `+`/`-`
+    SetByte(u8),
+}
+
+/// Writes the instruction as standard brainfuck
+/// 
+/// Optimized instructions are written as the loop or run they replaced, except
+/// `MulAdd` which is written as its part of the loop body: the mul-add loop itself
+/// is the run of `MulAdd` followed by `Clear`, see `expand`.
+/// `Random`, `DumpTape`, `Signal`, `PrintPointer` and `PeekInput` aren't standard
+/// brainfuck and are written as `%`, `@`, `!`, `$` and `?`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::PointerInc => write!(f, ">"),
+            Instruction::PointerDec => write!(f, "<"),
+            Instruction::ByteInc => write!(f, "+"),
+            Instruction::ByteDec => write!(f, "-"),
+            Instruction::Output => write!(f, "."),
+            Instruction::Input => write!(f, ","),
+            Instruction::OpenLoop => write!(f, "["),
+            Instruction::CloseLoop => write!(f, "]"),
+            Instruction::Random => write!(f, "%"),
+            Instruction::DumpTape => write!(f, "@"),
+            Instruction::Signal => write!(f, "!"),
+            Instruction::PrintPointer => write!(f, "$"),
+            Instruction::PeekInput => write!(f, "?"),
+            Instruction::AddByte(n) if n <= 128 => write!(f, "{}", "+".repeat(n as usize)),
+            Instruction::AddByte(n) => write!(f, "{}", "-".repeat(256 - n as usize)),
+            Instruction::MovePointer(n) if n >= 0 => write!(f, "{}", ">".repeat(n as usize)),
+            Instruction::MovePointer(n) => write!(f, "{}", "<".repeat(n.unsigned_abs())),
+            Instruction::Clear => write!(f, "[-]"),
+            Instruction::Scan(n) => write!(f, "[{}]", Instruction::MovePointer(n)),
+            Instruction::MulAdd { offset, factor } => {
+                // The move back is mirrored rather than negated, `-isize::MIN` overflows
+                let back = match offset >= 0 {
+                    true => "<",
+                    false => ">",
+                };
+                write!(f, "{}{}{}", Instruction::MovePointer(offset), Instruction::AddByte(factor), back.repeat(offset.unsigned_abs()))
+            },
+            Instruction::Cat => write!(f, "[.,]"),
+            Instruction::SetByte(n) => write!(f, "{}{}", Instruction::Clear, Instruction::AddByte(n)),
+        }
+    }
+}
+
+#[derive (Debug)]
+struct StackItem {
+    index: usize,
+}
+
+/// What `step_back` needs to revert a step
+#[derive (Debug)]
+struct UndoEntry {
+    instruction_pointer: usize,
+    data_pointer: usize,
+    steps: u64,
+    /// Cells the step may write, with their previous values
+    cells: Vec<(usize, u8)>,
+    output_len: usize,
+    output_count: usize,
+    /// Loop stack length and top item before the step, for `OptLevel::None`
+    loop_stack: (usize, Option<usize>),
+    /// Input bytes the step consumed
+    input: Vec<u8>,
+    /// Output bytes `tail_output` dropped from the front of the buffer during the step
+    dropped_output: Vec<u8>,
+    peeked_input: Option<u8>,
+    pending_newline: bool,
+}
+
+/// Reads a line from stdin and returns it whole, empty once stdin is closed
+/// 
+/// Prints a prompt before every attempt if `prompt` is set. Retries failed reads up to
+/// `MAX_INPUT_ATTEMPTS` times
+fn read_stdin(prompt: bool) -> Result<String, BFError> {
+    for _ in 0..MAX_INPUT_ATTEMPTS {
+        if prompt {
+            println!("Enter a char: ");
+        }
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).is_ok() {
+            return Ok(line);
+        }
+    }
+
+    Err(BFError::InputFailed(MAX_INPUT_ATTEMPTS))
+}
+
+/// Removes a leading `#!` line, only if the source starts with `#!` at byte 0
+fn strip_shebang(source: &str) -> &str {
+    match source.starts_with("#!") {
+        true => source.find('\n').map_or("", |i| &source[i + 1..]),
+        false => source,
+    }
+}
+
+/// The standard brainfuck instructions
+pub(crate) fn default_instructions() -> HashMap<char, Instruction> {
+    HashMap::from([
+        ('>', Instruction::PointerInc),
+        ('<', Instruction::PointerDec),
+        ('+', Instruction::ByteInc),
+        ('-', Instruction::ByteDec),
+        ('.', Instruction::Output),
+        (',', Instruction::Input),
+        ('[', Instruction::OpenLoop),
+        (']', Instruction::CloseLoop),
+    ])
+}
+
+/// Runs `producer_src` and feeds its output as the input of `consumer_src`
+/// 
+/// Both programs run on their own interpreter built from `config`, returns the output
+/// of the consumer
+/// 
+/// # Examples
+/// ```ignore
+/// // Passes "HI" through a program that echoes its input until EOF
+/// let config = BFInterpreterConfig { eof_behavior: EofBehavior::Zero, ..Default::default() };
+/// let output = pipe("++++++++[>+++++++++<-]>.+.", ",[.,]", config)?;
+/// ```
+pub fn pipe(producer_src: &str, consumer_src: &str, config: BFInterpreterConfig) -> Result<Vec<u8>, BFError> {
+    let mut producer = BFInterpreter::new(Some(config.clone()))?;
+    let input = producer.run_bytes(producer_src)?;
+
+    let mut consumer = BFInterpreter::new(Some(config))?;
+    consumer.set_input(&input);
+    consumer.run_bytes(consumer_src)
+}
+
+/// What `,` writes to the current cell when there's no input left
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum EofBehavior {
+    /// Leaves the cell as it was
+    #[default]
+    Unchanged,
+    /// Sets the cell to 0
+    Zero,
+    /// Sets the cell to 255, i.e. -1
+    MaxValue,
+}
+
+/// What `,` does when no input was set with `set_input` or `on_input`
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum OnMissingInput {
+    /// Fails with `BFError::NoInput`, so library users can't hang on an unexpected `,`
+    #[default]
+    Error,
+    /// Applies `eof_behavior` as if the input was empty
+    Eof,
+    /// Prompts on stdin and waits for a line, for interactive use
+    Block,
+}
+
+/// What `<` does on the first cell of the tape
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum PointerUnderflow {
+    /// Fails with `BFError::PointerOutOfBounds`
+    #[default]
+    Error,
+    /// Leaves the pointer on cell 0, as some lenient interpreters do
+    /// 
+    /// Lets programs ported from those interpreters run, but it hides the bug in a
+    /// program that moves left of its tape by mistake
+    Clamp,
+}
+
+/// How output bytes are passed on before reaching stdout, callbacks or the buffer
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFilter {
+    /// Passes every byte as is
+    #[default]
+    Raw,
+    /// Replaces bytes that aren't printable ASCII, newline or tab with the substitute
+    /// 
+    /// Keeps terminals from misbehaving on control characters
+    Printable { substitute: u8 },
+}
+
+impl OutputFilter {
+    fn apply(self, byte: u8) -> u8 {
+        match self {
+            OutputFilter::Printable { substitute } if !matches!(byte, b' '..=b'~' | b'\n' | b'\t') => substitute,
+            _ => byte,
+        }
+    }
+}
+
+/// How output bytes become text in the strings returned by `run` and passed to `on_line`
+/// 
+/// Doesn't affect `run_bytes`, `output_bytes`, `on_output` or `echo_output`, which always
+/// get the bytes themselves.
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputEncoding {
+    /// Every byte becomes the char with the same code point, U+0000 to U+00FF
+    /// 
+    /// Lossless and one char per byte, but programs printing UTF-8 come out garbled,
+    /// e.g. `é` as `Ã©`
+    #[default]
+    Latin1,
+    /// Bytes are decoded as UTF-8, invalid sequences become U+FFFD
+    /// 
+    /// Right for programs printing text in any language, but binary output is mangled
+    /// and can't be recovered from the string
+    Utf8,
+    /// Printable ASCII, newline and tab are kept and every other byte is written as a
+    /// `\xNN` escape, with `\` escaped as `\\`
+    /// 
+    /// Lossless and safe to print anywhere, for binary output, but non-ASCII text is
+    /// unreadable
+    Raw,
+}
+
+impl OutputEncoding {
+    fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            OutputEncoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+            OutputEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            OutputEncoding::Raw => bytes.iter().map(|&b| match b {
+                b'\\' => "\\\\".to_string(),
+                b' '..=b'~' | b'\n' | b'\t' => (b as char).to_string(),
+                _ => format!("\\x{:02X}", b),
+            }).collect(),
+        }
+    }
+}
+
+/// Where output bytes go when no `on_output` or `on_line` callback takes them
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputSink {
+    /// Bytes are collected and returned by `run`, `run_bytes` and `output_bytes`
+    #[default]
+    Buffer,
+    /// Bytes are discarded, for programs run only for their side effects or final tape
+    /// 
+    /// Runs return empty output. `echo_output` and `max_output` still see every byte.
+    Null,
+}
+
+/// Whether a program's output depends on the `EofBehavior`, see `detect_eof_sensitivity`
+#[derive (Clone, Copy, Debug, PartialEq)]
+pub enum EofSensitivity {
+    /// Every `EofBehavior` gives the same result
+    Insensitive,
+    /// At least two `EofBehavior`s give different results, the program assumes one of them
+    Sensitive,
+}
+
+/// How `+` and `-` behave at the edges of the cell range
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub enum CellArithmetic {
+    /// 255 + 1 is 0 and 0 - 1 is 255, as in standard brainfuck
+    #[default]
+    Wrapping,
+    /// 255 + 1 stays 255 and 0 - 1 stays 0
+    Saturating,
+}
+
+#[derive (Clone)]
+pub struct BFInterpreterConfig {
+    /// Number of cells on the tape, `DEFAULT_TAPE_SIZE` if not set
+    pub tape_size: Option<usize>,
+    pub custom_instructions: Option<HashMap<char, Instruction>>,
+    /// Multi-char tokens for word based dialects, like `"incr"` for `ByteInc`
+    /// 
+    /// Replaces `custom_instructions` when set. At every position the longest matching
+    /// token wins. Tokens must not contain newlines.
+    pub custom_tokens: Option<HashMap<String, Instruction>>,
+    /// Char that starts a comment running to the end of the line
+    pub line_comment: Option<char>,
+    /// Skips chars that aren't mapped to an instruction, like standard brainfuck does
+    /// 
+    /// This is what lets programs carry prose comments. When disabled, any unmapped
+    /// char is rejected with `BFError::InvalidInstruction`. Enabled by default.
+    pub ignore_unknown_chars: bool,
+    /// Counts the unknown chars skipped while parsing, see `ignored_characters`
+    /// 
+    /// Whitespace isn't counted. Only useful with `ignore_unknown_chars`.
+    pub collect_ignored_chars: bool,
+    /// Optimization passes applied before running, see `OptLevel`
+    pub opt_level: OptLevel,
+    /// Maximum number of instructions executed before `try_run` gives up
+    pub max_steps: Option<u64>,
+    /// Maximum wall-clock time a run may take before failing with `BFError::Timeout`
+    /// 
+    /// The clock is only checked every `TIMEOUT_CHECK_INTERVAL` steps, so a run can
+    /// overshoot by the time those take, and a `,` blocked on stdin isn't interrupted.
+    pub max_duration: Option<Duration>,
+    /// Maximum length in bytes of the source of a program, checked before parsing
+    /// 
+    /// Rejects huge untrusted sources with `BFError::SourceTooLong` before they use any
+    /// parse-time memory. Comments count towards the length.
+    pub max_source_len: Option<usize>,
+    /// Reads programs from their last instruction to their first, for right-to-left dialects
+    /// 
+    /// The program `]-[+++` runs as `+++[-]`. Comments are still read left to right.
+    pub right_to_left: bool,
+    /// What `,` does once the input is exhausted
+    pub eof_behavior: EofBehavior,
+    /// Whether `,` may wait for input when no input was set with `set_input`
+    /// 
+    /// When disabled `,` applies `eof_behavior` right away instead, whatever
+    /// `on_missing_input` says, so batch jobs can't hang on an unexpected `,`. Enabled
+    /// by default.
+    pub block_on_input: bool,
+    /// What `,` does when no input was set, see `OnMissingInput`
+    /// 
+    /// Fails by default, use `OnMissingInput::Block` to prompt on stdin for interactive use
+    pub on_missing_input: OnMissingInput,
+    /// Prints "Enter a char:" before reading stdin for `,`, enabled by default
+    /// 
+    /// Setting the `BF_NO_PROMPT` environment variable disables it too, for scripts
+    /// that pipe input in. The variable is read when the interpreter is created.
+    pub input_prompt: bool,
+    /// Delivers the newline typed after a char on stdin as its own byte on the next `,`
+    /// 
+    /// Only the first char of every line typed at the prompt is read, and by default the
+    /// newline is dropped. Line oriented programs that stop on `\n` need it enabled.
+    pub include_input_newline: bool,
+    /// Also writes every output byte to stdout as soon as it's printed
+    pub echo_output: bool,
+    /// Filter applied to every output byte, see `OutputFilter`
+    /// 
+    /// Trace events still hold the bytes the program printed, before filtering
+    pub output_filter: OutputFilter,
+    /// How output bytes become text, see `OutputEncoding`
+    pub output_encoding: OutputEncoding,
+    /// Where output goes without a callback, see `OutputSink`
+    pub output_sink: OutputSink,
+    /// Flushes stdout after every byte written by `echo_output`
+    /// 
+    /// Needed by interactive programs so each char shows up right away, but slow for
+    /// large outputs. When disabled stdout is flushed once the run ends, or explicitly
+    /// with `flush`. Enabled by default.
+    pub flush_each_byte: bool,
+    /// File the collected output is written to if the interpreter is dropped during a panic
+    /// 
+    /// Keeps the partial output of a run that crashed on a bug instead of an error,
+    /// for debugging long runs. Stdout is flushed on drop regardless.
+    pub crash_output: Option<std::path::PathBuf>,
+    /// Keeps only the last N output bytes instead of the whole output
+    /// 
+    /// Bounds memory for long running generators where only the end of the output
+    /// matters. Older bytes are dropped silently.
+    pub tail_output: Option<usize>,
+    /// Maximum number of bytes a run may output before failing with `BFError::OutputLimitExceeded`
+    /// 
+    /// Counts every byte, including the ones sent to callbacks or dropped by `tail_output`
+    pub max_output: Option<usize>,
+    /// Lets `,` read input, enabled by default
+    /// 
+    /// When disabled `,` fails with `BFError::InputDisabled`, whatever the input source
+    pub allow_input: bool,
+    /// Lets instructions with host side effects run, enabled by default
+    /// 
+    /// When disabled `Signal` and `DumpTape` fail with `BFError::SideEffectDisabled`
+    pub allow_side_effects: bool,
+    /// Seed for the random bytes written by `Instruction::Random`
+    /// 
+    /// The generator is reseeded at the start of every run, so the same seed always
+    /// produces the same sequence and runs are reproducible. When not set every run
+    /// draws a fresh seed from system entropy, see `BFInterpreter::rng_seed`.
+    pub rng_seed: Option<u64>,
+    /// Grows the tape when the pointer moves past its right end instead of failing
+    /// 
+    /// The tape grows in chunks of `DEFAULT_TAPE_SIZE` cells, up to `max_tape_size`
+    pub growable_tape: bool,
+    /// Maximum number of cells a growable tape can grow to, unbounded if not set
+    pub max_tape_size: Option<usize>,
+    /// Stores only the cells that were written instead of the whole tape
+    /// 
+    /// Saves memory for programs that use a few cells far apart, like cell 0 and cell
+    /// 1 000 000, but every cell access becomes a hash lookup, so it's several times
+    /// slower for programs that use a compact region. Reading the whole tape with
+    /// `tape` allocates it in full.
+    pub sparse_tape: bool,
+    /// Value every cell starts with instead of 0, including cells added when the tape grows
+    /// 
+    /// Cells loaded with `load_tape_from` keep their loaded value. Standard brainfuck
+    /// starts on zeroed cells, so only change it to reproduce other interpreters.
+    pub fill_value: u8,
+    /// How `+` and `-` behave when a cell overflows
+    /// 
+    /// The optimizer assumes wrapping arithmetic, so with `Saturating` the program always
+    /// runs unoptimized regardless of `opt_level`
+    pub cell_arithmetic: CellArithmetic,
+    /// What `<` does on the first cell of the tape
+    /// 
+    /// Clamping makes moves order dependent (`<<>>` on cell 0 ends on cell 2), so with
+    /// `Clamp` the program always runs unoptimized regardless of `opt_level`
+    pub pointer_underflow: PointerUnderflow,
+    /// Counts how many times the body of every loop runs, see `loop_iterations`
+    pub profile_loops: bool,
+    /// Checks every loaded program for common mistakes, see `lint`
+    pub lint: bool,
+    /// How instructions are dispatched, see `Dispatch`
+    /// 
+    /// Only affects speed, both strategies produce the same results
+    pub dispatch: Dispatch,
+    /// Records every `ExecEvent` of a run, see `BFInterpreter::trace_log`
+    /// 
+    /// Every executed instruction adds at least one event, so only enable it for small
+    /// programs
+    pub record_trace: bool,
+    /// Number of steps `step_back` can undo, 0 disables the undo log
+    /// 
+    /// Every step keeps a small fixed size entry, a few dozen bytes, plus the input
+    /// bytes it read, so the log costs about `undo_depth` times that. Older entries are
+    /// dropped once it's full.
+    pub undo_depth: usize,
+    /// Cell the data pointer starts on, must be inside the tape
+    /// 
+    /// Gives programs room to move left without a growable tape
+    pub initial_pointer: usize,
+}
+
+impl Default for BFInterpreterConfig {
+    fn default() -> Self {
+        Self {
+            tape_size: None,
+            custom_instructions: None,
+            custom_tokens: None,
+            line_comment: None,
+            ignore_unknown_chars: true,
+            collect_ignored_chars: false,
+            opt_level: OptLevel::default(),
+            max_steps: None,
+            max_duration: None,
+            max_source_len: None,
+            right_to_left: false,
+            eof_behavior: EofBehavior::default(),
+            block_on_input: true,
+            on_missing_input: OnMissingInput::default(),
+            input_prompt: true,
+            include_input_newline: false,
+            echo_output: false,
+            crash_output: None,
+            output_filter: OutputFilter::default(),
+            output_encoding: OutputEncoding::default(),
+            output_sink: OutputSink::default(),
+            flush_each_byte: true,
+            tail_output: None,
+            max_output: None,
+            allow_input: true,
+            allow_side_effects: true,
+            rng_seed: None,
+            growable_tape: false,
+            fill_value: 0,
+            max_tape_size: None,
+            sparse_tape: false,
+            cell_arithmetic: CellArithmetic::default(),
+            pointer_underflow: PointerUnderflow::default(),
+            profile_loops: false,
+            lint: false,
+            dispatch: Dispatch::default(),
+            record_trace: false,
+            undo_depth: 0,
+            initial_pointer: 0,
+        }
+    }
+}
+
+type LineCallback = Box<dyn FnMut(&str)>;
+type OutputCallback = Box<dyn FnMut(u8)>;
+type BreakCondition = Box<dyn Fn(&BFInterpreter) -> bool>;
+type LoopCallback = Box<dyn FnMut(usize, u64)>;
+type InputCallback = Box<dyn FnMut() -> Option<u8>>;
+type SignalCallback = Box<dyn FnMut()>;
+#[cfg(feature = "async")]
+type AsyncInputCallback = Box<dyn FnMut() -> std::pin::Pin<Box<dyn std::future::Future<Output = Option<u8>>>>>;
+
+pub struct BFInterpreter {
+    instruction_pointer: usize,
+    instructions_map: HashMap<char, Instruction>,
+    tokens_map: Option<HashMap<String, Instruction>>,
+    line_comment: Option<char>,
+    ignore_unknown_chars: bool,
+    collect_ignored_chars: bool,
+    ignored_chars: HashMap<char, usize>,
+    right_to_left: bool,
+    instructions: Vec<Instruction>,
+    current_instruction: Instruction,
+    opt_level: OptLevel,
+    opt_report: OptReport,
+    jump_table: Vec<usize>,
+    dispatch: Dispatch,
+    handlers: Vec<dispatch::Handler>,
+    max_steps: Option<u64>,
+    max_duration: Option<Duration>,
+    max_source_len: Option<usize>,
+    /// When the current run times out, set on its first step
+    deadline: Option<Instant>,
+    steps: u64,
+
+    data_pointer: usize,
+    initial_pointer: usize,
+    data: tape::Tape,
+    sparse_tape: bool,
+    fill_value: u8,
+    initial_data: Vec<u8>,
+    tape_size: usize,
+    growable_tape: bool,
+    max_tape_size: Option<usize>,
+    cell_arithmetic: CellArithmetic,
+    pointer_underflow: PointerUnderflow,
+
+    loop_stack: Vec<StackItem>,
+    /// Deepest loop nesting of the program, the most `loop_stack` ever holds
+    loop_depth: usize,
+
+    input: Option<VecDeque<u8>>,
+    input_callback: Option<InputCallback>,
+    #[cfg(feature = "async")]
+    async_input_callback: Option<AsyncInputCallback>,
+    signal_callback: Option<SignalCallback>,
+    eof_behavior: EofBehavior,
+    block_on_input: bool,
+    on_missing_input: OnMissingInput,
+    input_prompt: bool,
+    include_input_newline: bool,
+    pending_newline: bool,
+    /// Byte taken from the input callback by `PeekInput`, read before the callback is called again
+    peeked_input: Option<u8>,
+    echo_output: bool,
+    crash_output: Option<std::path::PathBuf>,
+    output_filter: OutputFilter,
+    output_encoding: OutputEncoding,
+    output_sink: OutputSink,
+    flush_each_byte: bool,
+    tail_output: Option<usize>,
+    max_output: Option<usize>,
+    /// Bytes output by the current run, for `max_output`
+    output_count: usize,
+    allow_input: bool,
+    allow_side_effects: bool,
+    output: Vec<u8>,
+    configured_rng_seed: Option<u64>,
+    rng_seed: u64,
+    rng: rng::Rng,
+
+    line_callback: Option<LineCallback>,
+    line_buffer: Vec<u8>,
+    output_callback: Option<OutputCallback>,
+    loop_callback: Option<LoopCallback>,
+    loop_counters: HashMap<usize, u64>,
+    profile_loops: bool,
+    lint: bool,
+    warnings: Vec<Warning>,
+    loop_profile: HashMap<usize, u64>,
+
+    breakpoints: HashSet<usize>,
+    hit_breakpoints: HashMap<usize, u64>,
+    /// Instruction pointer and step count `run_until_breakpoint` last stopped at
+    breakpoint_stop: Option<(usize, u64)>,
+    break_conditions: Vec<BreakCondition>,
+
+    record_trace: bool,
+    trace: Vec<ExecEvent>,
+
+    undo_depth: usize,
+    undo_log: VecDeque<UndoEntry>,
+    /// Input bytes read by the current step
+    undo_input: Vec<u8>,
+    /// Output bytes dropped by `tail_output` in the current step
+    undo_output: Vec<u8>,
+    /// Input bytes given back by `step_back`, read again before any other input
+    undone_input: VecDeque<u8>,
+
+    recording: Option<(std::path::PathBuf, Session)>,
+}
+
+impl fmt::Debug for BFInterpreter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BFInterpreter")
+            .field("instruction_pointer", &self.instruction_pointer)
+            .field("instructions_map", &self.instructions_map)
+            .field("tokens_map", &self.tokens_map)
+            .field("right_to_left", &self.right_to_left)
+            .field("ignore_unknown_chars", &self.ignore_unknown_chars)
+            .field("collect_ignored_chars", &self.collect_ignored_chars)
+            .field("ignored_chars", &self.ignored_chars)
+            .field("instructions", &self.instructions)
+            .field("current_instruction", &self.current_instruction)
+            .field("opt_level", &self.opt_level)
+            .field("opt_report", &self.opt_report)
+            .field("dispatch", &self.dispatch)
+            .field("max_steps", &self.max_steps)
+            .field("max_duration", &self.max_duration)
+            .field("max_source_len", &self.max_source_len)
+            .field("steps", &self.steps)
+            .field("data_pointer", &self.data_pointer)
+            .field("initial_pointer", &self.initial_pointer)
+            .field("data", &self.data)
+            .field("sparse_tape", &self.sparse_tape)
+            .field("fill_value", &self.fill_value)
+            .field("initial_data", &self.initial_data)
+            .field("tape_size", &self.tape_size)
+            .field("growable_tape", &self.growable_tape)
+            .field("max_tape_size", &self.max_tape_size)
+            .field("cell_arithmetic", &self.cell_arithmetic)
+            .field("pointer_underflow", &self.pointer_underflow)
+            .field("loop_stack", &self.loop_stack)
+            .field("loop_depth", &self.loop_depth)
+            .field("input", &self.input)
+            .field("eof_behavior", &self.eof_behavior)
+            .field("block_on_input", &self.block_on_input)
+            .field("on_missing_input", &self.on_missing_input)
+            .field("input_prompt", &self.input_prompt)
+            .field("include_input_newline", &self.include_input_newline)
+            .field("pending_newline", &self.pending_newline)
+            .field("peeked_input", &self.peeked_input)
+            .field("echo_output", &self.echo_output)
+            .field("crash_output", &self.crash_output)
+            .field("output_filter", &self.output_filter)
+            .field("output_encoding", &self.output_encoding)
+            .field("output_sink", &self.output_sink)
+            .field("flush_each_byte", &self.flush_each_byte)
+            .field("tail_output", &self.tail_output)
+            .field("max_output", &self.max_output)
+            .field("output_count", &self.output_count)
+            .field("allow_input", &self.allow_input)
+            .field("allow_side_effects", &self.allow_side_effects)
+            .field("output", &self.output)
+            .field("rng_seed", &self.rng_seed)
+            .field("rng", &self.rng)
+            .field("line_buffer", &self.line_buffer)
+            .field("loop_counters", &self.loop_counters)
+            .field("profile_loops", &self.profile_loops)
+            .field("lint", &self.lint)
+            .field("warnings", &self.warnings)
+            .field("loop_profile", &self.loop_profile)
+            .field("breakpoints", &self.breakpoints)
+            .field("hit_breakpoints", &self.hit_breakpoints)
+            .field("breakpoint_stop", &self.breakpoint_stop)
+            .field("record_trace", &self.record_trace)
+            .field("undo_depth", &self.undo_depth)
+            .field("undo_log", &self.undo_log)
+            .field("undone_input", &self.undone_input)
+            .field("trace", &self.trace)
+            .field("recording", &self.recording)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for BFInterpreter {
+    fn drop(&mut self) {
+        // Errors can't be reported from here, the output is saved on a best effort basis
+        let _ = self.flush();
+
+        if let (true, Some(path)) = (std::thread::panicking(), &self.crash_output) {
+            let _ = std::fs::write(path, self.buffered_output());
+        }
+    }
+}
+
+impl BFInterpreter {
+    /// Creates a new BFInterpreter with the default config
+    /// 
+    /// You can pass a custom config to change the tape size and add custom instructions
+    /// 
+    /// Returns an error if the config is inconsistent, e.g. when the line comment char
+    /// is also mapped to an instruction or the tape size is 0
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// // Interpreter with default config
+    /// let mut interpreter = BFInterpreter::new(None)?;
+    /// 
+    /// // Interpreter with custom config
+    /// let mut custom_instructions = HashMap::new();
+    /// custom_instructions.insert('D', Instruction::PointerInc);
+    /// custom_instructions.insert('A', Instruction::PointerDec);
+    /// custom_instructions.insert('W', Instruction::ByteInc);
+    /// custom_instructions.insert('S', Instruction::ByteDec);
+    /// ...
+    /// 
+    /// let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+    ///    tape_size: Some(1024),
+    ///    custom_instructions: Some(custom_instructions),
+    ///    ..Default::default()
+    /// }))?;
+    /// 
+    /// interpreter.run(...);
+    /// ```
+    pub fn new(config: Option<BFInterpreterConfig>) -> Result<Self, BFError> {
+        let config = config.unwrap_or_default();
+
+        if let (Some(c), Some(map)) = (config.line_comment, &config.custom_instructions) {
+            if map.contains_key(&c) {
+                return Err(BFError::LineCommentCollision(c));
+            }
+        }
+
+        let tape_size = config.tape_size.unwrap_or(DEFAULT_TAPE_SIZE);
+        if tape_size == 0 {
+            return Err(BFError::EmptyTape);
+        }
+        if config.initial_pointer >= tape_size {
+            return Err(BFError::InvalidInitialPointer { pointer: config.initial_pointer, tape_size });
+        }
+
+        Ok(Self {
+            instruction_pointer: 0,
+            instructions_map: config.custom_instructions.unwrap_or_else(default_instructions),
+            tokens_map: config.custom_tokens,
+            line_comment: config.line_comment,
+            ignore_unknown_chars: config.ignore_unknown_chars,
+            collect_ignored_chars: config.collect_ignored_chars,
+            ignored_chars: HashMap::new(),
+            right_to_left: config.right_to_left,
+            instructions: Vec::new(),
+            current_instruction: Instruction::Output,
+            opt_level: config.opt_level,
+            opt_report: OptReport::default(),
+            jump_table: Vec::new(),
+            dispatch: config.dispatch,
+            handlers: Vec::new(),
+            max_steps: config.max_steps,
+            max_duration: config.max_duration,
+            max_source_len: config.max_source_len,
+            deadline: None,
+            steps: 0,
+            data_pointer: config.initial_pointer,
+            initial_pointer: config.initial_pointer,
+            data: tape::Tape::new(tape_size, config.sparse_tape, config.fill_value, &[]),
+            sparse_tape: config.sparse_tape,
+            fill_value: config.fill_value,
+            initial_data: Vec::new(),
+            tape_size,
+            growable_tape: config.growable_tape,
+            max_tape_size: config.max_tape_size,
+            cell_arithmetic: config.cell_arithmetic,
+            pointer_underflow: config.pointer_underflow,
+            loop_stack: Vec::new(),
+            loop_depth: 0,
+            input: None,
+            input_callback: None,
+            #[cfg(feature = "async")]
+            async_input_callback: None,
+            signal_callback: None,
+            eof_behavior: config.eof_behavior,
+            block_on_input: config.block_on_input,
+            on_missing_input: config.on_missing_input,
+            input_prompt: config.input_prompt && std::env::var_os("BF_NO_PROMPT").is_none(),
+            include_input_newline: config.include_input_newline,
+            pending_newline: false,
+            peeked_input: None,
+            echo_output: config.echo_output,
+            crash_output: config.crash_output,
+            output_filter: config.output_filter,
+            output_encoding: config.output_encoding,
+            output_sink: config.output_sink,
+            flush_each_byte: config.flush_each_byte,
+            tail_output: config.tail_output,
+            max_output: config.max_output,
+            output_count: 0,
+            allow_input: config.allow_input,
+            allow_side_effects: config.allow_side_effects,
+            output: Vec::new(),
+            configured_rng_seed: config.rng_seed,
+            rng_seed: 0,
+            rng: rng::Rng::new(0),
+            line_callback: None,
+            line_buffer: Vec::new(),
+            output_callback: None,
+            loop_callback: None,
+            loop_counters: HashMap::new(),
+            profile_loops: config.profile_loops,
+            lint: config.lint,
+            warnings: Vec::new(),
+            loop_profile: HashMap::new(),
+            breakpoints: HashSet::new(),
+            hit_breakpoints: HashMap::new(),
+            breakpoint_stop: None,
+            break_conditions: Vec::new(),
+            record_trace: config.record_trace,
+            undo_depth: config.undo_depth,
+            undo_log: VecDeque::new(),
+            undo_input: Vec::new(),
+            undo_output: Vec::new(),
+            undone_input: VecDeque::new(),
+            trace: Vec::new(),
+            recording: None,
+        })
+    }
+
+    /// Switches output to line mode
+    /// 
+    /// Instead of being collected into the value returned by `run`, output is buffered
+    /// until a newline is printed and the completed line (without the newline) is passed
+    /// to `callback`. Call `flush_line` after the program ends to receive any trailing
+    /// output that wasn't terminated by a newline.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let mut interpreter = BFInterpreter::new(None)?;
+    /// interpreter.on_line(|line| println!("[bf] {}", line));
+    /// 
+    /// interpreter.run_from_file("./log.bf");
+    /// interpreter.flush_line();
+    /// ```
+    pub fn on_line<F: FnMut(&str) + 'static>(&mut self, callback: F) {
+        self.line_callback = Some(Box::new(callback));
+    }
+
+    /// Passes any buffered partial line to the line callback
+    pub fn flush_line(&mut self) {
+        if self.line_buffer.is_empty() {
+            return;
+        }
+
+        let line = self.output_encoding.decode(&std::mem::take(&mut self.line_buffer));
+        if let Some(callback) = self.line_callback.as_mut() {
+            callback(&line);
+        }
+    }
+
+    /// Flushes the output echoed to stdout, see `flush_each_byte`
+    pub fn flush(&mut self) -> Result<(), BFError> {
+        match self.echo_output {
+            true => io::stdout().flush().map_err(|e| BFError::Io(e.to_string())),
+            false => Ok(()),
+        }
+    }
+
+    /// Passes every output byte to `callback` as soon as it's printed
+    /// 
+    /// Takes precedence over line mode, and the output is no longer collected into the
+    /// value returned by `run`
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let mut interpreter = BFInterpreter::new(None)?;
+    /// interpreter.on_output(move |byte| socket.write_all(&[byte]).unwrap());
+    /// ```
+    pub fn on_output<F: FnMut(u8) + 'static>(&mut self, callback: F) {
+        self.output_callback = Some(Box::new(callback));
+    }
+
+    /// Calls `callback` every time a `]` jumps back to the start of its loop
+    /// 
+    /// The callback gets the index of the loop's `[` and the number of iterations
+    /// completed since the loop was entered. Loops collapsed by the optimizer into a
+    /// single instruction, like `[-]`, run in one step and never call it.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.on_loop_iteration(|start, count| println!("loop {} ran {} times", start, count));
+    /// ```
+    pub fn on_loop_iteration<F: FnMut(usize, u64) + 'static>(&mut self, callback: F) {
+        self.loop_callback = Some(Box::new(callback));
+    }
+
+    /// Makes `,` read the next byte from `callback`, `None` meaning EOF
+    /// 
+    /// Takes precedence over the input set with `set_input` and over stdin, and `None`
+    /// applies the configured `EofBehavior`
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let mut interpreter = BFInterpreter::new(None)?;
+    /// interpreter.on_input(move || keyboard.last_key());
+    /// ```
+    pub fn on_input<F: FnMut() -> Option<u8> + 'static>(&mut self, callback: F) {
+        self.input_callback = Some(Box::new(callback));
+    }
+
+    /// Calls `callback` every time `Instruction::Signal` runs
+    /// 
+    /// Lets programs trigger host side effects like a beep or a log marker. `Signal`
+    /// must be mapped to a char in `custom_instructions`, and does nothing without a
+    /// callback.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let custom_instructions = bf_dialect! {
+    ///     '>' => PointerInc, '<' => PointerDec, '+' => ByteInc, '-' => ByteDec,
+    ///     '.' => Output, ',' => Input, '[' => OpenLoop, ']' => CloseLoop;
+    ///     '!' => Signal,
+    /// };
+    /// ...
+    /// interpreter.on_signal(|| print!("\x07"));
+    /// ```
+    pub fn on_signal<F: FnMut() + 'static>(&mut self, callback: F) {
+        self.signal_callback = Some(Box::new(callback));
+    }
+
+    /// Preloads the bytes read by `,` instead of prompting on stdin
+    /// 
+    /// Once the input is exhausted `,` applies the configured `EofBehavior`. Set an
+    /// empty input to get that behavior from the first `,` on.
+    pub fn set_input(&mut self, input: &[u8]) {
+        self.input = Some(input.iter().copied().collect());
+    }
+
+    /// Returns the contents of the tape
+    /// 
+    /// Borrowed from a dense tape, allocated in full from a sparse one
+    pub fn tape(&self) -> Cow<'_, [u8]> {
+        self.data.as_slice()
+    }
+
+    /// Returns the output buffered so far
+    /// 
+    /// Every byte is buffered as soon as it's printed, so it can be read between `step`
+    /// calls to watch the output of a run in progress. It's kept after a failed
+    /// `try_run`, so it shows what the program printed before the error. Output sent to
+    /// `on_output` or `on_line` callbacks isn't buffered.
+    pub fn output_bytes(&self) -> &[u8] {
+        self.buffered_output()
+    }
+
+    /// Returns the index of the cell the data pointer is on
+    pub fn data_pointer(&self) -> usize {
+        self.data_pointer
+    }
+
+    /// Returns the index of the next instruction to run
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    /// Whether the loaded program ran past its last instruction, after which `step` does nothing
+    /// 
+    /// Also true before any program is loaded. `reset` and loading a program rewind it,
+    /// and `step_back` can undo the last step.
+    pub fn is_halted(&self) -> bool {
+        self.instruction_pointer >= self.instructions.len()
+    }
+
+    /// Returns the next instruction to run, `None` once the program has ended
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        self.instructions.get(self.instruction_pointer).copied()
+    }
+
+    /// Returns the current cell read as a two's complement signed value
+    /// 
+    /// Useful when debugging programs that rely on wrapping subtraction, where 255 means -1
+    pub fn current_cell_signed(&self) -> i64 {
+        self.data[self.data_pointer] as i8 as i64
+    }
+
+    /// Returns how many times each unknown char was skipped in the last parsed program
+    /// 
+    /// Helps catching typos like `›` instead of `>`, which are silently skipped as
+    /// comments. Always empty unless `collect_ignored_chars` is set in the config.
+    pub fn ignored_characters(&self) -> HashMap<char, usize> {
+        self.ignored_chars.clone()
+    }
+
+    /// Returns how many times the body of every loop ran, keyed by the index of its `[`
+    /// 
+    /// Only filled when `profile_loops` is set in the config, and cleared on `reset`.
+    /// Indices refer to the parsed instructions, after optimization, and loops collapsed
+    /// by the optimizer into a single instruction, like `[-]`, aren't counted.
+    pub fn loop_iterations(&self) -> HashMap<usize, u64> {
+        self.loop_profile.clone()
+    }
+
+    /// Returns the execution state as a JSON document for external tools
+    /// 
+    /// Holds the instruction and data pointers, the step count, the number of loops
+    /// around the next instruction and the non-zero cells of the tape, in tape order.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.load("++[>+<-]")?;
+    /// interpreter.step()?;
+    /// assert_eq!(
+    ///     interpreter.state_json(),
+    ///     r#"{"instruction_pointer":1,"data_pointer":0,"steps":1,"loop_depth":0,"cells":[{"index":0,"value":1}]}"#,
+    /// );
+    /// ```
+    pub fn state_json(&self) -> String {
+        let loop_depth = (0..self.instruction_pointer.min(self.instructions.len()))
+            .filter(|&i| self.instructions[i] == Instruction::OpenLoop && self.jump_table[i] >= self.instruction_pointer)
+            .count();
+        let cells: Vec<String> = self.data.non_zero()
+            .iter()
+            .map(|(index, value)| format!("{{\"index\":{},\"value\":{}}}", index, value))
+            .collect();
+
+        format!(
+            "{{\"instruction_pointer\":{},\"data_pointer\":{},\"steps\":{},\"loop_depth\":{},\"cells\":[{}]}}",
+            self.instruction_pointer,
+            self.data_pointer,
+            self.steps,
+            loop_depth,
+            cells.join(","),
+        )
+    }
+
+    /// Whether both interpreters have the same tape, data pointer and instruction pointer
+    /// 
+    /// Output isn't compared since it may have gone to different callbacks. Instruction
+    /// pointers only match between programs parsed and optimized the same way.
+    pub fn state_eq(&self, other: &BFInterpreter) -> bool {
+        self.data == other.data
+            && self.data_pointer == other.data_pointer
+            && self.instruction_pointer == other.instruction_pointer
+    }
+
+    /// Returns the seed used for `Instruction::Random` in the last run
+    /// 
+    /// Log it to replay a run with the same random bytes by setting `rng_seed` in the config
+    pub fn rng_seed(&self) -> u64 {
+        self.rng_seed
+    }
+
+    /// Writes the raw tape to a file, one byte per cell in tape order
+    /// 
+    /// Cells are single bytes so there's no endianness to account for, and the file is
+    /// exactly as long as the tape
+    pub fn dump_tape_to<P: AsRef<std::path::Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.data.as_slice())
+    }
+
+    /// Loads a tape written by `dump_tape_to`
+    /// 
+    /// The loaded bytes become the initial tape contents of every following run instead
+    /// of zeroes. A file shorter than the tape only fills its first cells and leaves the
+    /// rest at `fill_value`, a file longer than the tape is rejected with `InvalidData`.
+    pub fn load_tape_from<P: AsRef<std::path::Path>>(&mut self, path: P) -> io::Result<()> {
+        let bytes = std::fs::read(path)?;
+
+        if bytes.len() > self.tape_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Tape file has {} cells but the tape size is {}", bytes.len(), self.tape_size),
+            ));
+        }
+
+        self.initial_data = bytes;
+        self.reset_tape();
+
+        Ok(())
+    }
+
+    /// Grows or shrinks the tape to `new_size` cells
+    /// 
+    /// New cells are set to `fill_value` and shrinking drops the cells past the new end, including
+    /// the ones loaded with `load_tape_from`. Meant to be called between runs, the next
+    /// run starts on a tape of the new size.
+    /// 
+    /// Returns `BFError::TapeResizeOutOfBounds` and leaves the tape untouched if the
+    /// data pointer, or the `initial_pointer` it's reset to, would be left outside of
+    /// the tape.
+    pub fn resize_tape(&mut self, new_size: usize) -> Result<(), BFError> {
+        let pointer = self.data_pointer.max(self.initial_pointer);
+        if pointer >= new_size {
+            return Err(BFError::TapeResizeOutOfBounds { pointer, size: new_size });
+        }
+
+        self.tape_size = new_size;
+        self.data.resize(new_size);
+        self.initial_data.truncate(new_size);
+
+        Ok(())
+    }
+
+    /// Records the program and the input it reads to a session file on every run
+    /// 
+    /// The file is written when a run ends, even if it fails, and can be replayed with
+    /// `Session::read` and `Session::config` or the `--replay` flag of the binary. The
+    /// program is stored as standard brainfuck, see `source`, so it replays without the
+    /// custom dialect. Every setting that changes what the program does is stored with
+    /// it, see `Session`. Only input actually read is stored, so the replay sees EOF
+    /// where the run did.
+    pub fn start_recording<P: AsRef<std::path::Path>>(&mut self, path: P) {
+        self.recording = Some((path.as_ref().to_path_buf(), Session::default()));
+    }
+
+    /// Stops writing the session file started with `start_recording`
+    pub fn stop_recording(&mut self) {
+        self.recording = None;
+    }
+
+    /// Runs the program in the file and returns its output
+    /// 
+    /// A first line starting with `#!` is skipped, so scripts can be made executable
+    /// with a shebang like `#!/usr/bin/env bf`
+    pub fn run_from_file(&mut self, file_path: &str) -> String {
+        let instructions = match std::fs::read_to_string(file_path) {
+            Ok(v) => v,
+            Err(_) => panic!("Error reading file {}", file_path),
+        };
+
+        self.run(strip_shebang(&instructions))
+    }
+
+    /// Runs the program and returns its output
+    /// 
+    /// Output bytes become text according to `output_encoding`, by default every byte
+    /// becomes the char with the same code point. Use `run_bytes` to get the raw output.
+    /// 
+    /// Panics if the program can't run to completion, see `try_run`
+    pub fn run(&mut self, instructions: &str) -> String {
+        match self.try_run(instructions) {
+            Ok(v) => v,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Runs the program and returns its output, or an error if it exceeds the configured limits
+    /// 
+    /// Every executed instruction counts as a step, including the ones merged by the optimizer
+    pub fn try_run(&mut self, instructions: &str) -> Result<String, BFError> {
+        self.load(instructions)?;
+        self.execute()?;
+
+        Ok(self.output_string())
+    }
+
+    /// Runs the program and returns its output as raw bytes
+    /// 
+    /// Same as `try_run` but without converting the output to a `String`
+    pub fn run_bytes(&mut self, instructions: &str) -> Result<Vec<u8>, BFError> {
+        self.load(instructions)?;
+        self.execute()?;
+
+        Ok(self.buffered_output().to_vec())
+    }
+
+    /// Runs a program given as instructions instead of source, skipping parsing
+    /// 
+    /// For code generators and programs loaded with `from_bytecode`. The brackets are
+    /// still checked, and the configured `opt_level` is applied.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// use Instruction::*;
+    /// let output = interpreter.run_compiled(&[ByteInc, ByteInc, OpenLoop, ByteDec, CloseLoop])?;
+    /// ```
+    pub fn run_compiled(&mut self, instructions: &[Instruction]) -> Result<String, BFError> {
+        parse::check_brackets(instructions)?;
+
+        self.init(instructions.to_vec());
+        self.execute()?;
+
+        Ok(self.output_string())
+    }
+
+    /// Parses the program incrementally from a reader and runs it
+    /// 
+    /// Unlike `try_run` the source is never held in memory as a whole, it's read and
+    /// parsed in blocks of a few kilobytes, even when it's all on one line. Only the
+    /// parsed instructions are kept, which saves memory for very large programs.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let file = std::fs::File::open("./huge.bf")?;
+    /// let output = interpreter.run_from_reader(file)?;
+    /// ```
+    pub fn run_from_reader<R: io::Read>(&mut self, reader: R) -> Result<String, BFError> {
+        // Reading one byte past the limit is enough to know the source is too long
+        let limit = self.max_source_len.map_or(u64::MAX, |max| (max as u64).saturating_add(1));
+        let mut reader = io::Read::take(reader, limit);
+        let mut parser = self.parser();
+        let mut block = vec![0; READ_CHUNK_SIZE];
+        // Bytes read but not parsed yet, a char or token cut off at the end of a block
+        let mut pending: Vec<u8> = Vec::new();
+        let mut len = 0;
+
+        loop {
+            let n = match io::Read::read(&mut reader, &mut block) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(BFError::Io(e.to_string())),
+            };
+            len += n;
+            self.check_source_len(len)?;
+            pending.extend_from_slice(&block[..n]);
+
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(v) => v.len(),
+                // The rest of the char is in the next block
+                Err(e) if n > 0 && e.error_len().is_none() => e.valid_up_to(),
+                Err(e) => return Err(BFError::Io(e.to_string())),
+            };
+            let source = std::str::from_utf8(&pending[..valid]).unwrap();
+
+            if n == 0 {
+                parser.push_str(source)?;
+                break;
+            }
+            let parsed = parser.push_chunk(source)?;
+            pending.drain(..parsed);
+        }
+
+        let ignored = parser.take_ignored();
+        let instructions = parser.finish()?;
+        self.ignored_chars = ignored;
+        self.init(instructions);
+        self.execute()?;
+
+        Ok(self.output_string())
+    }
+
+    /// Parses the program and resets the interpreter without running anything
+    /// 
+    /// Use it to run a program piece by piece with `step` and `run_until_breakpoint`
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.load("+++[>+<-]>.")?;
+    /// interpreter.add_breakpoint(4);
+    /// 
+    /// while interpreter.run_until_breakpoint()? {
+    ///     println!("{:?}", interpreter.tape());
+    /// }
+    /// ```
+    pub fn load(&mut self, instructions: &str) -> Result<(), BFError> {
+        self.check_source_len(instructions.len())?;
+
+        let mut parser = self.parser();
+        parser.push_str(instructions)?;
+
+        let ignored = parser.take_ignored();
+        let instructions = parser.finish()?;
+        self.ignored_chars = ignored;
+        self.init(instructions);
+        Ok(())
+    }
+
+    /// Fails if a source of `len` bytes is longer than `max_source_len`
+    fn check_source_len(&self, len: usize) -> Result<(), BFError> {
+        match self.max_source_len {
+            Some(max) if len > max => Err(BFError::SourceTooLong { len, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parses the program and checks it has at most `max_instructions` instructions
+    /// 
+    /// Counts instructions as written, before optimization, so comments don't count and
+    /// `+++` is three. Nothing is run and the loaded program is left untouched.
+    pub fn validate_size(&self, instructions: &str, max_instructions: usize) -> Result<(), BFError> {
+        let mut parser = self.parser();
+        parser.push_str(instructions)?;
+
+        let count = parser.finish()?.len();
+        match count > max_instructions {
+            true => Err(BFError::ProgramTooLarge { count, max: max_instructions }),
+            false => Ok(()),
+        }
+    }
+
+    fn output_string(&self) -> String {
+        self.output_encoding.decode(self.buffered_output())
+    }
+
+    fn buffered_output(&self) -> &[u8] {
+        match self.tail_output {
+            Some(n) => &self.output[self.output.len().saturating_sub(n)..],
+            None => &self.output,
+        }
+    }
+
+    /// Runs the current instruction, the default `Dispatch::Match` strategy
+    fn dispatch_match(&mut self) -> Result<(), BFError> {
+        match self.current_instruction {
+            Instruction::PointerInc => self.pointer_inc()?,
+            Instruction::PointerDec => self.pointer_dec()?,
+            Instruction::ByteInc => self.byte_inc(),
+            Instruction::ByteDec => self.byte_dec(),
+            Instruction::Output => self.output()?,
+            Instruction::Input => self.input()?,
+            Instruction::OpenLoop => self.jump(),
+            Instruction::CloseLoop => self.jump(),
+            Instruction::Random => self.data[self.data_pointer] = self.rng.next_u8(),
+            Instruction::DumpTape => self.dump_tape()?,
+            Instruction::Signal => self.signal()?,
+            Instruction::PrintPointer => self.print_pointer()?,
+            Instruction::PeekInput => self.peek_input()?,
+            Instruction::AddByte(n) => self.add_byte(n),
+            Instruction::MovePointer(n) => self.move_pointer(n)?,
+            Instruction::Clear => self.data[self.data_pointer] = 0,
+            Instruction::Scan(n) => self.scan(n)?,
+            Instruction::MulAdd { offset, factor } => self.mul_add(offset, factor)?,
+            Instruction::Cat => self.cat()?,
+            Instruction::SetByte(n) => self.data[self.data_pointer] = n,
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self) -> Result<(), BFError> {
+        let mut result = Ok(true);
+        while let Ok(true) = result {
+            result = self.step();
+        }
+
+        self.finish_run(result.map(|_| ()))
+    }
+
+    /// Ends a run that stopped with `result`, shared by every way of running a program
+    fn finish_run(&mut self, result: Result<(), BFError>) -> Result<(), BFError> {
+        // Output printed before an error is flushed too
+        if !self.flush_each_byte {
+            self.flush()?;
+        }
+        if let Some((path, session)) = &self.recording {
+            session.write(path).map_err(|e| BFError::Io(e.to_string()))?;
+        }
+
+        result
+    }
+
+    /// Runs the next instruction of the loaded program
+    /// 
+    /// Returns `false` without doing anything once the program has ended
+    pub fn step(&mut self) -> Result<bool, BFError> {
+        let index = self.instruction_pointer;
+        self.current_instruction = match self.instructions.get(self.instruction_pointer) {
+            Some(v) => *v,
+            None => return Ok(false),
+        };
+
+        self.check_limits()?;
+        let undo = (self.undo_depth > 0).then(|| self.undo_entry());
+        self.steps += 1;
+
+        // println!("Instruction: {:#?}", self);
+
+        match self.dispatch {
+            Dispatch::Table => (self.handlers[index])(self, self.current_instruction)?,
+            Dispatch::Match => self.dispatch_match()?,
+        }
+
+        if let Some(mut entry) = undo {
+            entry.input = std::mem::take(&mut self.undo_input);
+            entry.dropped_output = std::mem::take(&mut self.undo_output);
+            if self.undo_log.len() == self.undo_depth {
+                self.undo_log.pop_front();
+            }
+            self.undo_log.push_back(entry);
+        }
+
+        self.record(ExecEvent::Step {
+            index,
+            instruction: self.current_instruction,
+            data_pointer: self.data_pointer,
+            cell: self.data[self.data_pointer],
+        });
+        self.instruction_pointer += 1;
+
+        Ok(true)
+    }
+
+    /// Runs the loaded program until `n` instructions have run since it was loaded or
+    /// reset, or until it ends
+    /// 
+    /// Does nothing if more than `n` instructions have already run. Together with
+    /// `reset` this allows bisecting the step at which something goes wrong.
+    pub fn run_to_step(&mut self, n: u64) -> Result<(), BFError> {
+        while self.steps < n && self.step()? {}
+
+        Ok(())
+    }
+
+    /// Returns the number of instructions run since the program was loaded or reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Returns every event of the current run, in order
+    /// 
+    /// Empty unless `record_trace` is enabled in the config
+    pub fn trace_log(&self) -> &[ExecEvent] {
+        &self.trace
+    }
+
+    fn record(&mut self, event: ExecEvent) {
+        if self.record_trace {
+            self.trace.push(event);
+        }
+    }
+
+    /// Stops `run_until_breakpoint` before the instruction at `index` runs
+    pub fn add_breakpoint(&mut self, index: usize) {
+        self.breakpoints.insert(index);
+    }
+
+    /// Stops `run_until_breakpoint` before the instruction at `index` runs for the `hits`th time
+    /// 
+    /// The breakpoint is removed once it's hit. A `hits` of 0 behaves like 1.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// // Break before the loop body at index 4 runs for the 500th time
+    /// interpreter.add_hit_count_breakpoint(4, 500);
+    /// ```
+    pub fn add_hit_count_breakpoint(&mut self, index: usize, hits: u64) {
+        self.hit_breakpoints.insert(index, hits.max(1));
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) {
+        self.breakpoints.remove(&index);
+        self.hit_breakpoints.remove(&index);
+    }
+
+    /// Stops `run_until_breakpoint` as soon as `condition` holds
+    /// 
+    /// The condition is checked before every instruction, so keep it cheap
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// // Break when cell 5 equals 42
+    /// interpreter.add_conditional_breakpoint(|bf| bf.tape()[5] == 42);
+    /// ```
+    pub fn add_conditional_breakpoint<F: Fn(&BFInterpreter) -> bool + 'static>(&mut self, condition: F) {
+        self.break_conditions.push(Box::new(condition));
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.hit_breakpoints.clear();
+        self.break_conditions.clear();
+    }
+
+    /// Runs the loaded program until it reaches a breakpoint or ends
+    /// 
+    /// Breakpoints are checked before every instruction, including the first one, and
+    /// calling it again resumes past the breakpoint it stopped at. Returns `true` when
+    /// stopped at a breakpoint and `false` once the program has ended.
+    pub fn run_until_breakpoint(&mut self) -> Result<bool, BFError> {
+        // The hit of the instruction it stopped at was already counted
+        if self.breakpoint_stop == Some((self.instruction_pointer, self.steps)) && !self.step()? {
+            return Ok(false);
+        }
+
+        while self.instruction_pointer < self.instructions.len() {
+            // Counted first so hits aren't missed when another breakpoint stops here too
+            let hit = self.hit_count_reached();
+            if hit || self.breakpoints.contains(&self.instruction_pointer) || self.break_conditions.iter().any(|c| c(self)) {
+                self.breakpoint_stop = Some((self.instruction_pointer, self.steps));
+                return Ok(true);
+            }
+
+            self.step()?;
+        }
+
+        Ok(false)
+    }
+
+    /// Counts a hit on the current instruction, returns `true` when its count runs out
+    fn hit_count_reached(&mut self) -> bool {
+        let remaining = match self.hit_breakpoints.get_mut(&self.instruction_pointer) {
+            Some(v) => v,
+            None => return false,
+        };
+
+        *remaining -= 1;
+        match *remaining {
+            0 => {
+                self.hit_breakpoints.remove(&self.instruction_pointer);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns whether the program terminates within `budget` steps
+    /// 
+    /// The output of the program is discarded: it isn't buffered, passed to callbacks or
+    /// echoed, and the run isn't recorded. Everything is attached again afterwards.
+    pub fn halts_within(&mut self, instructions: &str, budget: u64) -> Result<bool, BFError> {
+        let max_steps = self.max_steps.replace(budget);
+        let result = self.detached(OutputSink::Null, |bf| bf.try_run(instructions));
+        self.max_steps = max_steps;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(BFError::StepLimitExceeded(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs `f` with output going only to `sink`, without callbacks, echo, `tail_output`
+    /// or recording, then attaches them again
+    fn detached<T>(&mut self, sink: OutputSink, f: impl FnOnce(&mut Self) -> T) -> T {
+        let output_callback = self.output_callback.take();
+        let line_callback = self.line_callback.take();
+        let recording = self.recording.take();
+        let tail_output = self.tail_output.take();
+        let echo_output = std::mem::replace(&mut self.echo_output, false);
+        let output_sink = std::mem::replace(&mut self.output_sink, sink);
+
+        let result = f(self);
+
+        self.output_callback = output_callback;
+        self.line_callback = line_callback;
+        self.recording = recording;
+        self.tail_output = tail_output;
+        self.echo_output = echo_output;
+        self.output_sink = output_sink;
+        result
+    }
+
+    /// Runs the program on `input` once with every `EofBehavior` and compares the results
+    /// 
+    /// Helps finding out which EOF convention a ported program assumes. The whole output,
+    /// the final tape and the data pointer are compared, whatever sink the output is
+    /// configured to go to, and a run that fails, e.g. by hitting `max_steps`, counts as
+    /// a result too. Set `max_steps` in the config, otherwise a program that loops forever
+    /// on one of the behaviors never returns. Output isn't passed to callbacks, echoed
+    /// or recorded. Replaces any input set with `set_input`.
+    pub fn detect_eof_sensitivity(&mut self, instructions: &str, input: &[u8]) -> Result<EofSensitivity, BFError> {
+        // Parse errors don't depend on the behavior, report them instead of comparing them
+        self.load(instructions)?;
+
+        let eof_behavior = self.eof_behavior;
+        let mut results = Vec::new();
+
+        for behavior in [EofBehavior::Unchanged, EofBehavior::Zero, EofBehavior::MaxValue] {
+            self.eof_behavior = behavior;
+            self.set_input(input);
+            let output = self.detached(OutputSink::Buffer, |bf| bf.run_bytes(instructions));
+            results.push((output.map_err(|e| e.to_string()), self.tape().into_owned(), self.data_pointer));
+        }
+        self.eof_behavior = eof_behavior;
+
+        match results.windows(2).all(|w| w[0] == w[1]) {
+            true => Ok(EofSensitivity::Insensitive),
+            false => Ok(EofSensitivity::Sensitive),
+        }
+    }
+
+    /// Returns the index of the bracket matching the one at `index` in the last parsed program
+    /// 
+    /// Returns `None` if there's no bracket at `index`. Indices refer to the parsed
+    /// instructions, after optimization.
+    pub fn matching_bracket(&self, index: usize) -> Option<usize> {
+        match self.instructions.get(index)? {
+            Instruction::OpenLoop | Instruction::CloseLoop => Some(self.jump_table[index]),
+            _ => None,
+        }
+    }
+
+    /// Returns the `(open, close)` bracket indices of every loop in the last parsed program
+    /// 
+    /// Nested loops are separate entries, ordered by their `[`. Indices refer to the
+    /// parsed instructions, after optimization, so loops collapsed by the optimizer
+    /// aren't listed.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.load("+[,[.,]]")?;
+    /// assert_eq!(interpreter.loops(), [(1, 7), (3, 6)]);
+    /// ```
+    pub fn loops(&self) -> Vec<(usize, usize)> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .filter(|&(_, &instruction)| instruction == Instruction::OpenLoop)
+            .map(|(i, _)| (i, self.jump_table[i]))
+            .collect()
+    }
+
+    /// Returns warnings for likely mistakes in the last parsed program
+    /// 
+    /// Only filled when `lint` is set in the config. Warnings don't stop the program
+    /// from running, see `Warning` for what's reported.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.load("-<>+")?;
+    /// assert_eq!(interpreter.lint(), [Warning::DecrementOnZero(0), Warning::CancellingMoves(1)]);
+    /// ```
+    pub fn lint(&self) -> Vec<Warning> {
+        self.warnings.clone()
+    }
+
+    /// Returns the indices of instructions in the last parsed program that can never run
+    /// 
+    /// For example the body of a loop that starts right after another loop ends (the
+    /// cell is always 0 there), or anything after `+[]`. The analysis is conservative,
+    /// instructions it doesn't report may still be unreachable.
+    pub fn unreachable_instructions(&self) -> Vec<usize> {
+        analysis::unreachable_instructions(&self.instructions, &self.jump_table, self.zeroed_tape())
+    }
+
+    /// Returns the basic blocks of the last parsed program and the jumps between them
+    /// 
+    /// Indices refer to the parsed instructions, after optimization, see `instructions`.
+    /// Render it with graphviz through `Cfg::to_dot`.
+    pub fn control_flow_graph(&self) -> Cfg {
+        Cfg::new(&self.instructions, &self.jump_table)
+    }
+
+    /// Returns what the optimizer did to the last parsed program
+    /// 
+    /// Lists how many instructions each pass merged and how many loops of each kind
+    /// were collapsed. Printing it gives one line per pass.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.load(&std::fs::read_to_string("./mandelbrot.b")?)?;
+    /// println!("{}", interpreter.optimization_report());
+    /// ```
+    pub fn optimization_report(&self) -> OptReport {
+        self.opt_report
+    }
+
+    /// Returns the last parsed program, after optimization
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Returns the last parsed program as standard brainfuck
+    /// 
+    /// Every instruction is written with its canonical char, so comments, custom
+    /// instruction chars and the original formatting are not preserved. Optimized
+    /// instructions are written as the code they replaced, see `expand`.
+    pub fn source(&self) -> String {
+        optimizer::expand(&self.instructions).iter().map(|v| v.to_string()).collect()
+    }
+
+    /// Returns the indices of the `[` of loops in the last parsed program that may never end
+    /// 
+    /// This is a heuristic, not a decision procedure. It flags simple loops whose body
+    /// can't bring the cell that controls them to 0 from every starting value, like
+    /// `[]`, `[>+<]` or `[--]`. A flagged loop may still end for the values it actually
+    /// sees, and loops with nested loops or input are never flagged even if they hang.
+    pub fn possibly_infinite_loops(&self) -> Vec<usize> {
+        analysis::possibly_infinite_loops(&self.instructions, &self.jump_table)
+    }
+
+    /// Estimates how many bytes the last parsed program outputs, without running it
+    /// 
+    /// Only works for output that doesn't depend on input: every loop that prints must
+    /// run a number of times known from the code alone, like `++++[>.<-]`. Returns
+    /// `None` otherwise. Assumes the program runs to completion without an error.
+    pub fn estimate_output_len(&self) -> Option<usize> {
+        analysis::output_len(&self.instructions, &self.jump_table, self.zeroed_tape())
+    }
+
+    /// Returns the range of cells the last parsed program may touch, relative to the cell it starts on
+    /// 
+    /// Useful to size the tape, and `0..=0` means the pointer never moves. Only works if
+    /// every loop leaves the pointer where it found it, like `[->+<]`. Returns `None`
+    /// for loops like `[>]` that move it by an amount depending on the cells.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.load(">>+[-<+>]<<")?;
+    /// assert_eq!(interpreter.reachable_cells(), Some(0..=2));
+    /// ```
+    pub fn reachable_cells(&self) -> Option<std::ops::RangeInclusive<isize>> {
+        analysis::reachable_cells(&self.instructions)
+    }
+
+    /// Serializes the last parsed program to the compact bytecode format
+    /// 
+    /// The result can be loaded back with `from_bytecode`. Fails with
+    /// `BFError::ProgramTooLarge` for programs of more than `u32::MAX` instructions,
+    /// the most the format can count.
+    pub fn to_bytecode(&self) -> Result<Vec<u8>, BFError> {
+        bytecode::to_bytecode(&self.instructions)
+    }
+
+    /// Returns the index of the cell at `offset` from the data pointer
+    /// 
+    /// Grows the tape if the cell is past its end and the tape is growable
+    fn cell_at(&mut self, offset: isize) -> Result<usize, BFError> {
+        let out_of_bounds = BFError::PointerOutOfBounds {
+            pointer: (self.data_pointer as isize).saturating_add(offset),
+            index: self.instruction_pointer,
+        };
+        let cell = self.data_pointer.checked_add_signed(offset).ok_or(out_of_bounds.clone())?;
+
+        if cell >= self.data.len() {
+            match self.growable_tape {
+                true => self.grow_tape(cell)?,
+                false => return Err(out_of_bounds),
+            }
+        }
+
+        Ok(cell)
+    }
+
+    /// Grows the tape so it contains `cell`, respecting `max_tape_size`
+    fn grow_tape(&mut self, cell: usize) -> Result<(), BFError> {
+        let max = self.max_tape_size.unwrap_or(usize::MAX);
+        if cell >= max {
+            return Err(BFError::TapeLimitExceeded(max));
+        }
+
+        let size = (cell + 1).next_multiple_of(DEFAULT_TAPE_SIZE).min(max);
+        self.data.resize(size);
+
+        Ok(())
+    }
+
+    fn pointer_inc(&mut self) -> Result<(), BFError> {
+        self.move_pointer(1)
+    }
+
+    fn pointer_dec(&mut self) -> Result<(), BFError> {
+        self.move_pointer(-1)
+    }
+
+    fn byte_inc(&mut self) {
+        let cell = &mut self.data[self.data_pointer];
+        *cell = match self.cell_arithmetic {
+            CellArithmetic::Wrapping => cell.wrapping_add(1),
+            CellArithmetic::Saturating => cell.saturating_add(1),
+        };
+    }
+
+    fn byte_dec(&mut self) {
+        let cell = &mut self.data[self.data_pointer];
+        *cell = match self.cell_arithmetic {
+            CellArithmetic::Wrapping => cell.wrapping_sub(1),
+            CellArithmetic::Saturating => cell.saturating_sub(1),
+        };
+    }
+
+    fn add_byte(&mut self, value: u8) {
+        self.data[self.data_pointer] = self.data[self.data_pointer].wrapping_add(value);
+    }
+
+    fn move_pointer(&mut self, offset: isize) -> Result<(), BFError> {
+        let underflows = offset < 0 && offset.unsigned_abs() > self.data_pointer;
+        if underflows && self.pointer_underflow == PointerUnderflow::Clamp {
+            self.data_pointer = 0;
+            return Ok(());
+        }
+
+        self.data_pointer = self.cell_at(offset)?;
+        Ok(())
+    }
+
+    fn scan(&mut self, step: isize) -> Result<(), BFError> {
+        while self.data[self.data_pointer] != 0 {
+            self.move_pointer(step)?;
+        }
+        Ok(())
+    }
+
+    fn mul_add(&mut self, offset: isize, factor: u8) -> Result<(), BFError> {
+        let value = self.data[self.data_pointer];
+
+        // The original loop never runs its body on a zero cell
+        if value == 0 {
+            return Ok(());
+        }
+
+        let target = self.cell_at(offset)?;
+        self.data[target] = self.data[target].wrapping_add(value.wrapping_mul(factor));
+        Ok(())
+    }
+
+    /// Captures the state the current instruction is about to change
+    fn undo_entry(&mut self) -> UndoEntry {
+        self.undo_input.clear();
+        self.undo_output.clear();
+
+        let mut cells = vec![(self.data_pointer, self.data[self.data_pointer])];
+        if let Instruction::MulAdd { offset, .. } = self.current_instruction {
+            if let Some(target) = self.data_pointer.checked_add_signed(offset).filter(|&v| v < self.data.len()) {
+                cells.push((target, self.data[target]));
+            }
+        }
+
+        UndoEntry {
+            instruction_pointer: self.instruction_pointer,
+            data_pointer: self.data_pointer,
+            steps: self.steps,
+            cells,
+            output_len: self.output.len(),
+            output_count: self.output_count,
+            loop_stack: (self.loop_stack.len(), self.loop_stack.last().map(|v| v.index)),
+            input: Vec::new(),
+            dropped_output: Vec::new(),
+            peeked_input: self.peeked_input,
+            pending_newline: self.pending_newline,
+        }
+    }
+
+    /// Reverts the last instruction run by `step`, returns `false` if there's nothing to undo
+    /// 
+    /// Needs `undo_depth` in the config. The tape, pointers, step count and collected
+    /// output are restored, and input the instruction read or peeked is given back to be
+    /// read again. Output already passed to callbacks or echoed, trace events, loop counts
+    /// and tape growth are not reverted.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.load("+++>++")?;
+    /// interpreter.step()?;
+    /// interpreter.step_back();
+    /// assert_eq!(interpreter.tape()[0], 0);
+    /// ```
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.undo_log.pop_back() {
+            Some(v) => v,
+            None => return false,
+        };
+
+        self.instruction_pointer = entry.instruction_pointer;
+        self.data_pointer = entry.data_pointer;
+        self.steps = entry.steps;
+        for (index, value) in entry.cells {
+            self.data[index] = value;
+        }
+        self.output.splice(..0, entry.dropped_output);
+        self.output.truncate(entry.output_len);
+        self.output_count = entry.output_count;
+
+        let (len, top) = entry.loop_stack;
+        self.loop_stack.truncate(len);
+        if let (true, Some(index)) = (self.loop_stack.len() < len, top) {
+            self.loop_stack.push(StackItem { index });
+        }
+
+        // A byte a peek pulled from the callback, or the newline left by a line read from
+        // stdin, can't be fetched again, so it's given back like the consumed input
+        let fetched = entry.peeked_input.is_none().then_some(self.peeked_input).flatten();
+        let newline = (!entry.pending_newline && self.pending_newline).then_some(b'\n');
+        self.peeked_input = entry.peeked_input;
+        self.pending_newline = entry.pending_newline;
+
+        entry.input.into_iter().chain(fetched).chain(newline).rev().for_each(|v| self.undone_input.push_front(v));
+
+        true
+    }
+
+    /// Rewinds the last run one step at a time, yielding the state after every step back
+    /// 
+    /// A teaching aid to watch a computation unwind. Items are `state_json` documents,
+    /// from the state before the last instruction back to the oldest one in the undo
+    /// log, which is the start of the run if `undo_depth` covered every step. The
+    /// interpreter is really rewound, see `step_back` for what isn't restored.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.run("++>+")?;
+    /// for state in interpreter.replay_reverse() {
+    ///     println!("{}", state);
+    /// }
+    /// ```
+    pub fn replay_reverse(&mut self) -> impl Iterator<Item = String> + '_ {
+        std::iter::from_fn(move || self.step_back().then(|| self.state_json()))
+    }
+
+    /// Fails once the run has used up its steps or its time
+    fn check_limits(&mut self) -> Result<(), BFError> {
+        if self.max_steps.is_some_and(|max| self.steps >= max) {
+            return Err(BFError::StepLimitExceeded(self.steps));
+        }
+
+        if let Some(duration) = self.max_duration {
+            match self.steps {
+                0 => self.deadline = Some(Instant::now() + duration),
+                n if n % TIMEOUT_CHECK_INTERVAL == 0 && self.deadline.is_some_and(|d| Instant::now() >= d) => {
+                    return Err(BFError::Timeout(duration));
+                },
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs `[.,]` without going through the dispatch loop
+    /// 
+    /// Goes through `output` and `input` so sinks and the EOF behavior are respected,
+    /// and counts every iteration as a step so a cat that never sees a 0 still hits
+    /// the step limit
+    fn cat(&mut self) -> Result<(), BFError> {
+        while self.data[self.data_pointer] != 0 {
+            self.cat_iteration()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the body of `[.,]` once, see `cat`
+    fn cat_iteration(&mut self) -> Result<(), BFError> {
+        self.check_limits()?;
+        self.steps += 1;
+
+        self.output()?;
+        self.input()
+    }
+
+    fn output(&mut self) -> Result<(), BFError> {
+        self.write_output(self.data[self.data_pointer])
+    }
+
+    /// Same sources as `input`, but nothing is consumed and stdin is never read
+    fn peek_input(&mut self) -> Result<(), BFError> {
+        if !self.allow_input {
+            return Err(BFError::InputDisabled(self.instruction_pointer));
+        }
+
+        let value = match (self.undone_input.front(), self.input_callback.as_mut(), self.input.as_ref()) {
+            (Some(&v), _, _) => Some(v),
+            (None, Some(callback), _) => {
+                if self.peeked_input.is_none() {
+                    self.peeked_input = callback();
+                }
+                self.peeked_input
+            },
+            (None, None, Some(input)) => input.front().copied(),
+            (None, None, None) if self.pending_newline => Some(b'\n'),
+            (None, None, None) => None,
+        };
+
+        match (value, self.eof_behavior) {
+            (Some(v), _) => self.data[self.data_pointer] = v,
+            (None, EofBehavior::Unchanged) => (),
+            (None, EofBehavior::Zero) => self.data[self.data_pointer] = 0,
+            (None, EofBehavior::MaxValue) => self.data[self.data_pointer] = u8::MAX,
+        }
+
+        Ok(())
+    }
+
+    fn print_pointer(&mut self) -> Result<(), BFError> {
+        for digit in self.data_pointer.to_string().bytes() {
+            self.write_output(digit)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends a byte to the configured sinks, as if the program printed it
+    fn write_output(&mut self, byte: u8) -> Result<(), BFError> {
+        if let Some(max) = self.max_output {
+            if self.output_count >= max {
+                return Err(BFError::OutputLimitExceeded(max));
+            }
+        }
+        self.output_count += 1;
+
+        self.record(ExecEvent::Output { index: self.instruction_pointer, value: byte });
+        let byte = self.output_filter.apply(byte);
+
+        if self.echo_output {
+            io::stdout().write_all(&[byte]).map_err(|e| BFError::Io(e.to_string()))?;
+
+            if self.flush_each_byte {
+                self.flush()?;
+            }
+        }
+
+        if let Some(callback) = self.output_callback.as_mut() {
+            callback(byte);
+            return Ok(());
+        }
+
+        match self.line_callback.as_mut() {
+            None if self.output_sink == OutputSink::Null => (),
+            None => {
+                self.output.push(byte);
+
+                // Trimming only once the buffer doubles keeps pushes amortized O(1)
+                if let Some(n) = self.tail_output {
+                    if self.output.len() > 2 * n {
+                        let dropped = self.output.drain(..self.output.len() - n);
+                        // Kept for `step_back`, which needs them to restore the buffer
+                        if self.undo_depth > 0 {
+                            self.undo_output.extend(dropped);
+                        }
+                    }
+                }
+            },
+            Some(callback) => match byte {
+                b'\n' => {
+                    callback(&self.output_encoding.decode(&self.line_buffer));
+                    self.line_buffer.clear();
+                },
+                byte => self.line_buffer.push(byte),
+            },
+        }
+
+        Ok(())
+    }
+
+    fn dump_tape(&self) -> Result<(), BFError> {
+        self.check_side_effect()?;
+        let cells: Vec<String> = self.data.non_zero()
+            .iter()
+            .map(|(i, v)| format!("{}:{}", i, v))
+            .collect();
+
+        eprintln!("Tape at instruction {}, pointer at {}: [{}]", self.instruction_pointer, self.data_pointer, cells.join(" "));
+        Ok(())
+    }
+
+    /// Fails if the current instruction has a host side effect and they're disabled
+    fn check_side_effect(&self) -> Result<(), BFError> {
+        match self.allow_side_effects {
+            true => Ok(()),
+            false => Err(BFError::SideEffectDisabled {
+                instruction: self.current_instruction,
+                index: self.instruction_pointer,
+            }),
+        }
+    }
+
+    fn signal(&mut self) -> Result<(), BFError> {
+        self.check_side_effect()?;
+        if let Some(callback) = self.signal_callback.as_mut() {
+            callback();
+        }
+
+        Ok(())
+    }
+
+    fn input(&mut self) -> Result<(), BFError> {
+        if !self.allow_input {
+            return Err(BFError::InputDisabled(self.instruction_pointer));
+        }
+
+        let undone = self.undone_input.pop_front();
+        // Bytes taken from the peek buffer or the pending newline are restored by `step_back`
+        let buffered = undone.is_none() && match (self.input_callback.is_some(), self.input.is_some()) {
+            (true, _) => self.peeked_input.is_some(),
+            (false, false) => self.pending_newline,
+            (false, true) => false,
+        };
+        let value = match (undone, self.input_callback.as_mut(), self.input.as_mut()) {
+            (Some(v), _, _) => Some(v),
+            (None, Some(_), _) if self.peeked_input.is_some() => self.peeked_input.take(),
+            (None, Some(callback), _) => callback(),
+            (None, None, Some(input)) => input.pop_front(),
+            (None, None, None) if self.pending_newline => {
+                self.pending_newline = false;
+                Some(b'\n')
+            },
+            (None, None, None) => match (self.block_on_input, self.on_missing_input) {
+                (true, OnMissingInput::Error) => return Err(BFError::NoInput(self.instruction_pointer)),
+                (true, OnMissingInput::Block) => {
+                    let line = read_stdin(self.input_prompt)?;
+                    self.pending_newline = self.include_input_newline && line.len() > 1 && line.ends_with('\n');
+                    line.chars().next().map(|c| c as u8)
+                },
+                _ => None,
+            },
+        };
+        self.record(ExecEvent::Input { index: self.instruction_pointer, value });
+        // Bytes read again after `step_back` are already in the session
+        if let (None, Some((_, session)), Some(v)) = (undone, self.recording.as_mut(), value) {
+            session.input.push(v);
+        }
+        if let (true, false, Some(v)) = (self.undo_depth > 0, buffered, value) {
+            self.undo_input.push(v);
+        }
+
+        match (value, self.eof_behavior) {
+            (Some(v), _) => self.data[self.data_pointer] = v,
+            (None, EofBehavior::Unchanged) => (),
+            (None, EofBehavior::Zero) => self.data[self.data_pointer] = 0,
+            (None, EofBehavior::MaxValue) => self.data[self.data_pointer] = u8::MAX,
+        }
+
+        Ok(())
+    }
+
+    fn jump(&mut self) {
+        if self.opt_level != OptLevel::None {
+            let jump_to_match = match self.current_instruction {
+                Instruction::OpenLoop => self.data[self.data_pointer] == 0,
+                _ => self.data[self.data_pointer] != 0,
+            };
+
+            match (self.current_instruction, jump_to_match) {
+                (Instruction::OpenLoop, false) => self.enter_loop(self.instruction_pointer),
+                (Instruction::CloseLoop, true) => self.repeat_loop(self.jump_table[self.instruction_pointer]),
+                _ => (),
+            }
+
+            if jump_to_match {
+                self.instruction_pointer = self.jump_table[self.instruction_pointer];
+            }
+            return;
+        }
+
+        match self.current_instruction {
+            Instruction::CloseLoop => {
+                match self.data[self.data_pointer] {
+                    // If not 0 jump to the start of the loop, else leave it
+                    0 => { self.loop_stack.pop(); },
+                    _ => {
+                        self.instruction_pointer = self.loop_stack.last().unwrap().index;
+                        self.repeat_loop(self.instruction_pointer);
+                    },
+                }
+            },
+            Instruction::OpenLoop => {
+                match self.data[self.data_pointer] {
+                    // If 0 jump to the end of the loop, else continue
+                    0 => self.instruction_pointer = self.get_loop_end(),
+                    _ => {
+                        self.loop_stack.push(StackItem { index: self.instruction_pointer });
+                        self.enter_loop(self.instruction_pointer);
+                    },
+                }
+            },
+            _ => panic!("SHOULD NOT HAVE JUMPED")
+        }
+    }
+
+    /// Starts counting the iterations of the loop at `start` for `on_loop_iteration`
+    fn enter_loop(&mut self, start: usize) {
+        if self.loop_callback.is_some() {
+            self.loop_counters.insert(start, 0);
+        }
+        if self.profile_loops {
+            *self.loop_profile.entry(start).or_insert(0) += 1;
+        }
+    }
+
+    fn repeat_loop(&mut self, start: usize) {
+        if self.profile_loops {
+            *self.loop_profile.entry(start).or_insert(0) += 1;
+        }
+        if let Some(callback) = self.loop_callback.as_mut() {
+            let count = self.loop_counters.entry(start).or_insert(0);
+            *count += 1;
+            callback(start, *count);
+        }
+    }
+
+    fn get_loop_end(&self) -> usize {
+        let mut loopdepth = 1;
+        let mut pointer = self.instruction_pointer;
+
+        while loopdepth > 0 {
+            pointer += 1;
+
+            match self.instructions[pointer] {
+                Instruction::OpenLoop => loopdepth += 1,
+                Instruction::CloseLoop => loopdepth -= 1,
+                _ => (),
+            }
+        };
+
+        pointer
+    }
+
+    fn reset_tape(&mut self) {
+        self.data = tape::Tape::new(self.tape_size, self.sparse_tape, self.fill_value, &self.initial_data);
+    }
+
+    /// Whether every cell starts at 0, which the static analyses rely on
+    fn zeroed_tape(&self) -> bool {
+        self.fill_value == 0 && self.initial_data.iter().all(|&v| v == 0)
+    }
+
+    fn parser(&self) -> parse::Parser<'_> {
+        let dialect = match &self.tokens_map {
+            Some(tokens) => parse::Dialect::Tokens(tokens),
+            None => parse::Dialect::Chars(&self.instructions_map),
+        };
+
+        parse::Parser::new(dialect, self.line_comment, self.right_to_left, self.ignore_unknown_chars, self.collect_ignored_chars)
+    }
+
+    fn init(&mut self, instructions: Vec<Instruction>) {
+        let opt_level = match (self.cell_arithmetic, self.pointer_underflow) {
+            (CellArithmetic::Wrapping, PointerUnderflow::Error) => self.opt_level,
+            _ => OptLevel::None,
+        };
+        self.warnings = match self.lint {
+            true => lint::lint(&instructions, &optimizer::jump_table(&instructions), self.zeroed_tape()),
+            false => Vec::new(),
+        };
+        (self.instructions, self.opt_report) = optimizer::optimize(instructions, opt_level);
+        self.jump_table = optimizer::jump_table(&self.instructions);
+        self.loop_depth = parse::nesting_depth(&self.instructions);
+        if self.recording.is_some() {
+            let source = self.source();
+            if let Some((_, session)) = self.recording.as_mut() {
+                session.source = source;
+                session.eof_behavior = self.eof_behavior;
+                session.tape_size = self.tape_size;
+                session.fill_value = self.fill_value;
+                session.cell_arithmetic = self.cell_arithmetic;
+                session.pointer_underflow = self.pointer_underflow;
+                session.initial_pointer = self.initial_pointer;
+                session.growable_tape = self.growable_tape;
+                session.max_tape_size = self.max_tape_size;
+                session.include_input_newline = self.include_input_newline;
+            }
+        }
+        self.handlers = match self.dispatch {
+            Dispatch::Table => dispatch::handlers(&self.instructions),
+            Dispatch::Match => Vec::new(),
+        };
+
+        self.reset();
+    }
+
+    /// Rewinds the loaded program to its start
+    /// 
+    /// Clears the tape, output and step count but keeps the program and breakpoints.
+    /// Preloaded input that was already consumed is not restored.
+    pub fn reset(&mut self) {
+        self.instruction_pointer = 0;
+        self.steps = 0;
+
+        self.data_pointer = self.initial_pointer;
+        self.reset_tape();
+
+        // Sized for the whole program up front so it never reallocates while running
+        self.loop_stack.clear();
+        self.loop_stack.reserve(self.loop_depth);
+        self.loop_counters = HashMap::new();
+        self.loop_profile = HashMap::new();
+        self.pending_newline = false;
+        self.peeked_input = None;
+        self.breakpoint_stop = None;
+
+        self.output = Vec::new();
+        self.output_count = 0;
+        self.trace = Vec::new();
+        self.undo_log = VecDeque::new();
+        self.undone_input = VecDeque::new();
+        if let Some((_, session)) = self.recording.as_mut() {
+            session.input.clear();
+        }
+
+        self.rng_seed = self.configured_rng_seed.unwrap_or_else(rng::entropy_seed);
+        self.rng = rng::Rng::new(self.rng_seed);
+        if let Some((_, session)) = self.recording.as_mut() {
+            session.rng_seed = self.rng_seed;
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Warnings for common mistakes in programs that are still valid
+//!
+//! Runs over the parsed program before optimization, since the optimizer merges
+//! away the very patterns reported here. Like the other analyses it's
+//! conservative: cell values are only followed through straight-line code.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{analysis::Cells, Instruction};
+
+/// A likely mistake found by `BFInterpreter::lint`
+/// 
+/// Indices refer to the parsed program before optimization, i.e. they count the
+/// instructions of the source in execution order, without comments.
+#[derive (Clone, Copy, Debug, PartialEq)]
+pub enum Warning {
+    /// `-` on a cell that's always 0 there, so it wraps around to 255
+    DecrementOnZero(usize),
+    /// `[` of a loop that's never entered, its cell is always 0 there
+    LoopNeverEntered(usize),
+    /// `<>` or `><`, moves that cancel out, at the index of the first one
+    CancellingMoves(usize),
+}
+
+impl Warning {
+    /// Returns the index of the instruction the warning is about
+    pub fn index(&self) -> usize {
+        match *self {
+            Warning::DecrementOnZero(i) | Warning::LoopNeverEntered(i) | Warning::CancellingMoves(i) => i,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::DecrementOnZero(i) => write!(f, "Decrement at index {} wraps a cell that's always 0 to 255", i),
+            Warning::LoopNeverEntered(i) => write!(f, "Loop at index {} is never entered, its cell is always 0", i),
+            Warning::CancellingMoves(i) => write!(f, "Moves at index {} cancel out and do nothing", i),
+        }
+    }
+}
+
+/// Returns the warnings for a parsed program, ordered by index
+pub(crate) fn lint(instructions: &[Instruction], jump_table: &[usize], zeroed_tape: bool) -> Vec<Warning> {
+    let mut cells = Cells { pointer: 0, values: HashMap::new(), default: zeroed_tape.then_some(0) };
+    let mut warnings = Vec::new();
+    let mut i = 0;
+
+    while i < instructions.len() {
+        match instructions[i] {
+            Instruction::OpenLoop => match cells.get(0) {
+                Some(0) => {
+                    warnings.push(Warning::LoopNeverEntered(i));
+                    i = jump_table[i];
+                },
+                // Nothing is known inside the body, it may be on any iteration
+                _ => cells = Cells { pointer: 0, values: HashMap::new(), default: None },
+            },
+            Instruction::CloseLoop | Instruction::Scan(_) | Instruction::Cat => cells.forget(),
+            Instruction::PointerInc | Instruction::PointerDec => {
+                let (step, cancelled) = match instructions[i] {
+                    Instruction::PointerInc => (1, Instruction::PointerDec),
+                    _ => (-1, Instruction::PointerInc),
+                };
+
+                match instructions.get(i + 1) == Some(&cancelled) {
+                    true => {
+                        warnings.push(Warning::CancellingMoves(i));
+                        i += 1;
+                    },
+                    false => cells.pointer += step,
+                }
+            },
+            Instruction::MovePointer(n) => cells.pointer += n,
+            Instruction::ByteInc => cells.set(0, cells.get(0).map(|v| v.wrapping_add(1))),
+            Instruction::ByteDec => {
+                if cells.get(0) == Some(0) {
+                    warnings.push(Warning::DecrementOnZero(i));
+                }
+                cells.set(0, cells.get(0).map(|v| v.wrapping_sub(1)));
+            },
+            Instruction::AddByte(n) => cells.set(0, cells.get(0).map(|v| v.wrapping_add(n))),
+            Instruction::Clear => cells.set(0, Some(0)),
+            Instruction::SetByte(n) => cells.set(0, Some(n)),
+            Instruction::Input | Instruction::PeekInput | Instruction::Random => cells.set(0, None),
+            Instruction::MulAdd { offset, .. } => cells.set(offset, None),
+            Instruction::Output | Instruction::DumpTape | Instruction::Signal | Instruction::PrintPointer => (),
+        }
+
+        i += 1;
+    }
+
+    warnings
+}
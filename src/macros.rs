@@ -0,0 +1,42 @@
+//! Macros for defining dialects
+
+/// Builds the `custom_instructions` map of a dialect
+/// 
+/// Every one of the eight standard instructions must be mapped exactly once,
+/// otherwise the program doesn't compile. Extension instructions like `Random` go
+/// after a `;`.
+/// 
+/// # Examples
+/// ```ignore
+/// let wasd = bf_dialect! {
+///     'D' => PointerInc, 'A' => PointerDec, 'W' => ByteInc, 'S' => ByteDec,
+///     'O' => Output, 'I' => Input, '(' => OpenLoop, ')' => CloseLoop;
+///     '?' => Random,
+/// };
+/// ```
+#[macro_export]
+macro_rules! bf_dialect {
+    (
+        $($c:literal => $name:ident),+ $(,)?
+        $(; $($extension_c:literal => $extension:ident),+ $(,)?)?
+    ) => {{
+        // A struct literal fails to compile when a field is missing, repeated or unknown
+        #[allow(non_snake_case, dead_code)]
+        struct StandardInstructions {
+            PointerInc: (),
+            PointerDec: (),
+            ByteInc: (),
+            ByteDec: (),
+            Output: (),
+            Input: (),
+            OpenLoop: (),
+            CloseLoop: (),
+        }
+        let _ = StandardInstructions { $($name: ()),+ };
+
+        ::std::collections::HashMap::<char, $crate::Instruction>::from([
+            $(($c, $crate::Instruction::$name),)+
+            $($(($extension_c, $crate::Instruction::$extension),)+)?
+        ])
+    }};
+}
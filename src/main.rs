@@ -1,275 +1,95 @@
-use std::{io, collections::HashMap};
+use brainfuck_interpreter_rs::{bf_dialect, load_dialect, BFError, BFInterpreter, BFInterpreterConfig, OnMissingInput, Session};
 
-#[derive (Clone, Copy, Debug, PartialEq)]
-enum Instruction {
-    PointerInc,
-    PointerDec,
-    ByteInc,
-    ByteDec,
-    Output,
-    Input,
-    OpenLoop,
-    CloseLoop,
+/// Prints the output as text, or as space separated hex bytes with `--hex`
+fn print_output(output: &[u8], hex: bool) {
+    match hex {
+        true => println!("{}", output.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")),
+        false => println!("{}", output.iter().map(|&b| b as char).collect::<String>()),
+    }
 }
 
-#[derive (Debug)]
-struct StackItem {
-    index: usize,
-}
+/// Runs a session recorded with `start_recording`, feeding it the recorded input
+fn replay(path: &str, hex: bool) -> Result<(), BFError> {
+    let session = Session::read(path).map_err(|e| BFError::Io(e.to_string()))?;
 
-struct BFInterpreterConfig {
-    tape_size: Option<usize>,
-    custom_instructions: Option<HashMap<char, Instruction>>,
-}
+    let mut interpreter = BFInterpreter::new(Some(session.config()))?;
+    interpreter.set_input(&session.input);
+    interpreter.try_run(&session.source)?;
+    print_output(interpreter.output_bytes(), hex);
 
-#[derive (Debug)]
-struct BFInterpreter {
-    instruction_pointer: usize,
-    instructions_map: HashMap<char, Instruction>,
-    instructions: Vec<Instruction>,
-    current_instruction: Instruction,
+    Ok(())
+}
 
-    data_pointer: usize,
-    data: Vec<u8>,
+/// Runs a program file written in the dialect defined in the dialect file
+fn run_dialect(dialect_path: &str, program_path: &str, hex: bool) -> Result<(), BFError> {
+    let source = std::fs::read_to_string(program_path).map_err(|e| BFError::Io(e.to_string()))?;
 
-    loop_stack: Vec<StackItem>,
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(load_dialect(dialect_path)?),
+        on_missing_input: OnMissingInput::Block,
+        ..Default::default()
+    }))?;
+    interpreter.try_run(&source)?;
+    print_output(interpreter.output_bytes(), hex);
 
-    output: Vec<char>,
+    Ok(())
 }
 
-impl BFInterpreter {
-    /// Creates a new BFInterpreter with the default config
-    /// 
-    /// You can pass a custom config to change the tape size and add custom instructions
-    /// 
-    /// # Examples
-    /// ```
-    /// // Interpreter with default config
-    /// let mut interpreter = BFInterpreter::new(None);
-    /// 
-    /// // Interpreter with custom config
-    /// let mut custom_instructions = HashMap::new();
-    /// custom_instructions.insert('D', Instruction::PointerInc);
-    /// custom_instructions.insert('A', Instruction::PointerDec);
-    /// custom_instructions.insert('W', Instruction::ByteInc);
-    /// custom_instructions.insert('S', Instruction::ByteDec);
-    /// ...
-    /// 
-    /// let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
-    ///    tape_size: Some(1024),
-    ///   custom_instructions: Some(custom_instructions),
-    /// }));
-    /// 
-    /// interpreter.run(...);
-    /// ```
-    pub fn new(config: Option<BFInterpreterConfig>) -> Self {
-        let (tape_size, custom_instructions) = match config {
-            None => (None, None),
-            Some(v) => (v.tape_size, v.custom_instructions),
-        };
-
-        Self {
-            instruction_pointer: 0,
-            instructions_map: custom_instructions.unwrap_or(HashMap::from([
-                ('>', Instruction::PointerInc),
-                ('<', Instruction::PointerDec),
-                ('+', Instruction::ByteInc),
-                ('-', Instruction::ByteDec),
-                ('.', Instruction::Output),
-                (',', Instruction::Input),
-                ('[', Instruction::OpenLoop),
-                (']', Instruction::CloseLoop),
-            ])),
-            instructions: Vec::new(),
-            current_instruction: Instruction::Output,
-            data_pointer: 0,
-            data: vec![0; tape_size.unwrap_or(1024)],
-            loop_stack: Vec::new(),
-            output: Vec::new(),
-        }
-    }
+fn main() -> Result<(), BFError> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let hex = args.iter().any(|arg| arg == "--hex");
 
-    pub fn run_from_file(&mut self, file_path: &str) -> String {
-        let instructions = match std::fs::read_to_string(file_path) {
-            Ok(v) => v,
-            Err(_) => panic!("Error reading file {}", file_path),
+    if let Some(i) = args.iter().position(|arg| arg == "--replay") {
+        return match args.get(i + 1) {
+            Some(path) => replay(path, hex),
+            None => Err(BFError::Io("--replay needs a session file".to_string())),
         };
-
-        self.run(&instructions)
     }
 
-    pub fn run(&mut self, instructions: &str) -> String {
-        self.init(instructions);
-        
-        let closing_brackets = self.instructions.iter().filter(|&i| *i == Instruction::CloseLoop).count();
-        let opening_brackets = self.instructions.iter().filter(|&i| *i == Instruction::OpenLoop).count();
-
-        if closing_brackets != opening_brackets {
-            panic!("Unbalanced brackets");
+    if let Some(i) = args.iter().position(|arg| arg == "--dialect") {
+        return match (args.get(i + 1), args.get(i + 2)) {
+            (Some(dialect), Some(program)) => run_dialect(dialect, program, hex),
+            _ => Err(BFError::Io("--dialect needs a dialect file and a program file".to_string())),
         };
-
-        while self.instruction_pointer < self.instructions.len() {
-            self.current_instruction = match self.instructions.get(self.instruction_pointer) {
-                Some(v) => *v,
-                None => panic!("Error getting instruction"),
-            };
-
-            // println!("Instruction: {:#?}", self);
-
-            match self.instructions[self.instruction_pointer] {
-                Instruction::PointerInc => self.pointer_inc(),
-                Instruction::PointerDec => self.pointer_dec(),
-                Instruction::ByteInc => self.byte_inc(),
-                Instruction::ByteDec => self.byte_dec(),
-                Instruction::Output => self.output(),
-                Instruction::Input => self.input(),
-                Instruction::OpenLoop => self.jump(),
-                Instruction::CloseLoop => self.jump(),
-            }
-
-            self.instruction_pointer += 1;
-        }
-
-        self.output.iter().collect()
-    }
-
-    fn pointer_inc(&mut self) {
-        match self.data.get(self.data_pointer + 1) {
-            Some(_) => self.data_pointer += 1,
-            None => panic!("Out of bounds trying to increment pointer to {} at index {}", self.data_pointer + 1, self.instruction_pointer),
-        }
-    }
-
-    fn pointer_dec(&mut self) {
-        match self.data.get(self.data_pointer - 1) {
-            Some(_) => self.data_pointer -= 1,
-            None => panic!("Out of bounds trying to decrement pointer to {} at index {}", self.data_pointer + 1, self.instruction_pointer),
-        }
     }
 
-    fn byte_inc(&mut self) {
-        match self.data[self.data_pointer].checked_add(1) {
-            Some(v) => self.data[self.data_pointer] = v,
-            None => self.data[self.data_pointer] = 0,
-        }
-    }
-
-    fn byte_dec(&mut self) {
-        match self.data[self.data_pointer].checked_sub(1) {
-            Some(v) => self.data[self.data_pointer] = v,
-            None => self.data[self.data_pointer] = u8::MAX,
-        }
-    }
-
-    fn output(&mut self) {
-        self.output.push(self.data[self.data_pointer] as char);
-    }
-
-    fn input(&mut self) {
-        println!("Enter a char: ");
-
-        let mut line = String::new();
-        let input = io::stdin().read_line(&mut line);
-
-        match input {
-            Ok(_) => {
-                let c = line.chars().next().unwrap();
-                self.data[self.data_pointer] = c as u8;
-            },
-            Err(_) => self.input(),
-        }
-    }
-
-    fn jump(&mut self) {
-        match self.current_instruction {
-            Instruction::CloseLoop => {
-                match self.data[self.data_pointer] {
-                    // If not 0 jump to the start of the loop, else continue
-                    0 => (),
-                    _ => self.instruction_pointer = self.loop_stack.last().unwrap().index,
-                }
-            },
-            Instruction::OpenLoop => {
-                match self.data[self.data_pointer] {
-                    // If 0 jump to the end of the loop, else continue
-                    0 => self.instruction_pointer = self.get_loop_end(),
-                    _ => self.loop_stack.push(StackItem { index: self.instruction_pointer }),
-                }
-            },
-            _ => panic!("SHOULD NOT HAVE JUMPED")
-        }
-    }
-
-    fn get_loop_end(&self) -> usize {
-        let mut loopdepth = self.loop_stack.len();
-        let mut pointer = self.instruction_pointer;
-
-        while loopdepth > 0 {
-            pointer += 1;
-
-            match self.instructions[pointer] {
-                Instruction::OpenLoop => loopdepth += 1,
-                Instruction::CloseLoop => loopdepth -= 1,
-                _ => (),
-            }
-        };
-
-        pointer
-    }
-
-    fn init(&mut self, instructions: &str) {
-        self.instruction_pointer = 0;
-        self.instructions = instructions
-            .chars()
-            .enumerate()
-            .map(|(i,c)| match self.instructions_map.get(&c) {
-                Some(v) => *v,
-                None => panic!("Invalid instruction ({}) at index {}", c, i),
-            })
-            .collect();
-            
-        self.data_pointer = 0;
-        self.data = vec![0; self.data.len()];
-
-        self.loop_stack = Vec::new();
-
-        self.output = Vec::new();
-    }
-}
-
-fn main() {
-    // Print 3 hearts with default instructions
-    let mut interpreter = BFInterpreter::new(None);
-    let output = interpreter.run("+++>+++<[>.<-]");
-    println!("{}", output);
-
-    let custom_map = HashMap::from([
-        ('D', Instruction::PointerInc),
-        ('A', Instruction::PointerDec),
-        ('W', Instruction::ByteInc),
-        ('S', Instruction::ByteDec),
-        ('O', Instruction::Output),
-        ('I', Instruction::Input),
-        ('(', Instruction::OpenLoop),
-        (')', Instruction::CloseLoop),
-    ]);
+    // Print 3 hearts with default instructions, prompting for input when a program reads it
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        on_missing_input: OnMissingInput::Block,
+        ..Default::default()
+    }))?;
+    interpreter.run("+++>+++<[>.<-]");
+    print_output(interpreter.output_bytes(), hex);
+
+    let custom_map = bf_dialect! {
+        'D' => PointerInc,
+        'A' => PointerDec,
+        'W' => ByteInc,
+        'S' => ByteDec,
+        'O' => Output,
+        'I' => Input,
+        '(' => OpenLoop,
+        ')' => CloseLoop,
+    };
 
 
     // Print 3 hearts with custom instructions
     let config = BFInterpreterConfig {
         tape_size: Some(100),
         custom_instructions: Some(custom_map),
+        ..Default::default()
     };
-    let mut custom_interpreter = BFInterpreter::new(Some(config));
-    let output = custom_interpreter.run("WWWDWWWA(DOAS)");
-    println!("{}", output);
+    let mut custom_interpreter = BFInterpreter::new(Some(config))?;
+    custom_interpreter.run("WWWDWWWA(DOAS)");
+    print_output(custom_interpreter.output_bytes(), hex);
 
     // Print from file
-    let output = interpreter.run_from_file("./file.bf");
-    println!("{}", output);
+    interpreter.run_from_file("./file.bf");
+    print_output(interpreter.output_bytes(), hex);
     
     // Print Hello World
-    let output = interpreter.run("++++++++++[>+++++++>++++++++++>+++>+<<<<-]>++.>+.+++++++..+++.>++.<<+++++++++++++++.>.+++.------.--------.>+.>.");
-    println!("{}", output);
-    
+    interpreter.run("++++++++++[>+++++++>++++++++++>+++>+<<<<-]>++.>+.+++++++..+++.>++.<<+++++++++++++++.>.+++.------.--------.>+.>.");
+    print_output(interpreter.output_bytes(), hex);
+
+    Ok(())
 }
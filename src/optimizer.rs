@@ -0,0 +1,306 @@
+//! Optimization passes applied to the parsed instruction stream
+//!
+//! Which passes run is controlled by `OptLevel`:
+//!
+//! | Level   | Passes                                                       |
+//! |---------|--------------------------------------------------------------|
+//! | `None`  | No passes, instructions run exactly as written               |
+//! | `Basic` | Run-length coalescing, precomputed jump table                |
+//! | `Full`  | `Basic` plus clear, scan, mul-add and cat loop recognition,  |
+//! |         | clear loops followed by `+`/`-` fused into `SetByte`         |
+
+use std::fmt;
+
+use crate::Instruction;
+
+/// How aggressively the parsed program is optimized before running
+#[derive (Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    /// Runs every instruction as written, bit-for-bit equivalent to the naive interpreter
+    #[default]
+    None,
+    /// Coalesces runs of `+`/`-` and `>`/`<` and jumps through a precomputed jump table
+    Basic,
+    /// Also collapses clear loops (`[-]`), scan loops (`[>]`), mul-add loops (`[->++<]`)
+    /// and cat loops (`[.,]`), and fuses `[-]+++` into a single `SetByte`
+    Full,
+}
+
+/// What the optimizer did to the last parsed program, see `BFInterpreter::optimization_report`
+#[derive (Clone, Copy, Debug, Default, PartialEq)]
+pub struct OptReport {
+    /// Number of instructions before optimizing
+    pub instructions_before: usize,
+    /// Number of instructions after optimizing
+    pub instructions_after: usize,
+    /// Instructions removed by merging runs of `+`/`-` and `>`/`<`
+    pub runs_merged: usize,
+    /// Loops like `[-]` replaced by `Clear`
+    pub clear_loops: usize,
+    /// Loops like `[>]` replaced by `Scan`
+    pub scan_loops: usize,
+    /// Loops like `[->++<]` replaced by `MulAdd`
+    pub mul_add_loops: usize,
+    /// `[.,]` loops replaced by `Cat`
+    pub cat_loops: usize,
+    /// `Clear` followed by `AddByte` fused into `SetByte`
+    pub set_bytes_fused: usize,
+}
+
+impl fmt::Display for OptReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "instructions: {} -> {}", self.instructions_before, self.instructions_after)?;
+        writeln!(f, "run-length: {} instructions merged", self.runs_merged)?;
+        writeln!(f, "clear-loop: {} loops collapsed", self.clear_loops)?;
+        writeln!(f, "scan-loop: {} loops collapsed", self.scan_loops)?;
+        writeln!(f, "mul-add-loop: {} loops collapsed", self.mul_add_loops)?;
+        writeln!(f, "cat-loop: {} loops collapsed", self.cat_loops)?;
+        write!(f, "set-byte: {} clears fused", self.set_bytes_fused)
+    }
+}
+
+pub(crate) fn optimize(instructions: Vec<Instruction>, level: OptLevel) -> (Vec<Instruction>, OptReport) {
+    let mut report = OptReport { instructions_before: instructions.len(), ..Default::default() };
+
+    let instructions = match level {
+        OptLevel::None => instructions,
+        OptLevel::Basic => coalesce(&instructions, &mut report),
+        OptLevel::Full => {
+            let coalesced = coalesce(&instructions, &mut report);
+            fuse_set_byte(&collapse_loops(&coalesced, &mut report), &mut report)
+        },
+    };
+
+    report.instructions_after = instructions.len();
+    (instructions, report)
+}
+
+/// Expands optimized instructions back into the primitive instructions they replaced
+/// 
+/// The result runs the same as the input with `OptLevel::None`. A run of `MulAdd`
+/// followed by the `Clear` or `SetByte` that closes it becomes the loop it came from.
+/// Primitives and extensions like `Random` are kept as they are.
+pub fn expand(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut result = Vec::with_capacity(instructions.len());
+    let mut i = 0;
+
+    while i < instructions.len() {
+        // Mul-add loops are a run of `MulAdd` closed by a `Clear`, or by a `SetByte`
+        // when the loop was followed by `+`/`-`
+        let run = instructions[i..]
+            .iter()
+            .take_while(|v| matches!(v, Instruction::MulAdd { .. }))
+            .count();
+
+        let close = match instructions.get(i + run) {
+            Some(Instruction::Clear) => Some(0),
+            Some(&Instruction::SetByte(n)) => Some(n),
+            _ => None,
+        };
+
+        if let (true, Some(n)) = (run > 0, close) {
+            result.extend([Instruction::OpenLoop, Instruction::ByteDec]);
+            instructions[i..i + run].iter().for_each(|&v| expand_one(v, &mut result));
+            result.push(Instruction::CloseLoop);
+            expand_one(Instruction::AddByte(n), &mut result);
+            i += run + 1;
+            continue;
+        }
+
+        expand_one(instructions[i], &mut result);
+        i += 1;
+    }
+
+    result
+}
+
+/// Pushes the primitive instructions an optimized instruction replaced
+/// 
+/// A lone `MulAdd` is expanded into its part of the loop body
+fn expand_one(instruction: Instruction, result: &mut Vec<Instruction>) {
+    match instruction {
+        Instruction::AddByte(n) if n <= 128 => result.extend(std::iter::repeat_n(Instruction::ByteInc, n as usize)),
+        Instruction::AddByte(n) => result.extend(std::iter::repeat_n(Instruction::ByteDec, 256 - n as usize)),
+        Instruction::MovePointer(n) if n >= 0 => result.extend(std::iter::repeat_n(Instruction::PointerInc, n as usize)),
+        Instruction::MovePointer(n) => result.extend(std::iter::repeat_n(Instruction::PointerDec, n.unsigned_abs())),
+        Instruction::Clear => result.extend([Instruction::OpenLoop, Instruction::ByteDec, Instruction::CloseLoop]),
+        Instruction::Scan(n) => {
+            result.push(Instruction::OpenLoop);
+            expand_one(Instruction::MovePointer(n), result);
+            result.push(Instruction::CloseLoop);
+        },
+        Instruction::MulAdd { offset, factor } => {
+            // The move back is mirrored rather than negated, `-isize::MIN` overflows
+            let back = match offset >= 0 {
+                true => Instruction::PointerDec,
+                false => Instruction::PointerInc,
+            };
+            expand_one(Instruction::MovePointer(offset), result);
+            expand_one(Instruction::AddByte(factor), result);
+            result.extend(std::iter::repeat_n(back, offset.unsigned_abs()));
+        },
+        Instruction::Cat => result.extend([Instruction::OpenLoop, Instruction::Output, Instruction::Input, Instruction::CloseLoop]),
+        Instruction::SetByte(n) => {
+            expand_one(Instruction::Clear, result);
+            expand_one(Instruction::AddByte(n), result);
+        },
+        _ => result.push(instruction),
+    }
+}
+
+/// Maps every bracket to the index of its matching bracket
+/// 
+/// Indices of non-bracket instructions map to themselves. Expects balanced brackets.
+pub(crate) fn jump_table(instructions: &[Instruction]) -> Vec<usize> {
+    let mut table: Vec<usize> = (0..instructions.len()).collect();
+    let mut open = Vec::new();
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        match instruction {
+            Instruction::OpenLoop => open.push(i),
+            Instruction::CloseLoop => {
+                let start = open.pop().expect("Unbalanced brackets");
+                table[start] = i;
+                table[i] = start;
+            },
+            _ => (),
+        }
+    }
+
+    table
+}
+
+/// Merges runs of `+`/`-` into `AddByte` and runs of `>` or of `<` into `MovePointer`
+fn coalesce(instructions: &[Instruction], report: &mut OptReport) -> Vec<Instruction> {
+    let mut result: Vec<Instruction> = Vec::with_capacity(instructions.len());
+
+    for &instruction in instructions {
+        let merged = match (result.last(), instruction) {
+            (Some(Instruction::AddByte(n)), Instruction::ByteInc) => Some(Instruction::AddByte(n.wrapping_add(1))),
+            (Some(Instruction::AddByte(n)), Instruction::ByteDec) => Some(Instruction::AddByte(n.wrapping_sub(1))),
+            // Only moves in the same direction are merged, so the end of the run is as far
+            // as it goes and checking it fails exactly when checking every move does. Crafted
+            // bytecode can hold offsets that don't have room for one more move.
+            (Some(&Instruction::MovePointer(n)), Instruction::PointerInc) if n > 0 => n.checked_add(1).map(Instruction::MovePointer),
+            (Some(&Instruction::MovePointer(n)), Instruction::PointerDec) if n < 0 => n.checked_sub(1).map(Instruction::MovePointer),
+            _ => None,
+        };
+
+        match merged {
+            // Runs that cancel out are dropped entirely
+            Some(Instruction::AddByte(0)) => { result.pop(); },
+            Some(v) => *result.last_mut().unwrap() = v,
+            None => result.push(match instruction {
+                Instruction::ByteInc => Instruction::AddByte(1),
+                Instruction::ByteDec => Instruction::AddByte(u8::MAX),
+                Instruction::PointerInc => Instruction::MovePointer(1),
+                Instruction::PointerDec => Instruction::MovePointer(-1),
+                other => other,
+            }),
+        }
+    }
+
+    report.runs_merged += instructions.len() - result.len();
+    result
+}
+
+/// Replaces innermost loops matching a known idiom with a single instruction
+fn collapse_loops(instructions: &[Instruction], report: &mut OptReport) -> Vec<Instruction> {
+    let mut result: Vec<Instruction> = Vec::with_capacity(instructions.len());
+
+    for &instruction in instructions {
+        result.push(instruction);
+
+        if instruction != Instruction::CloseLoop {
+            continue;
+        }
+
+        // Only innermost loops are candidates, so the closest bracket must be their `[`
+        let start = match result[..result.len() - 1].iter().rposition(|&i| matches!(i, Instruction::OpenLoop | Instruction::CloseLoop)) {
+            Some(v) if result[v] == Instruction::OpenLoop => v,
+            _ => continue,
+        };
+        let body = &result[start + 1..result.len() - 1];
+
+        if let Some(collapsed) = collapse_loop(body) {
+            match collapsed[0] {
+                Instruction::Scan(_) => report.scan_loops += 1,
+                Instruction::Cat => report.cat_loops += 1,
+                Instruction::MulAdd { .. } => report.mul_add_loops += 1,
+                _ => report.clear_loops += 1,
+            }
+            result.truncate(start);
+            result.extend(collapsed);
+        }
+    }
+
+    result
+}
+
+fn collapse_loop(body: &[Instruction]) -> Option<Vec<Instruction>> {
+    match body {
+        [Instruction::AddByte(n)] if n % 2 == 1 => Some(vec![Instruction::Clear]),
+        [Instruction::MovePointer(n)] => Some(vec![Instruction::Scan(*n)]),
+        [Instruction::Output, Instruction::Input] => Some(vec![Instruction::Cat]),
+        _ => mul_add(body),
+    }
+}
+
+/// Fuses a `Clear` followed by an `AddByte` into a `SetByte`
+fn fuse_set_byte(instructions: &[Instruction], report: &mut OptReport) -> Vec<Instruction> {
+    let mut result: Vec<Instruction> = Vec::with_capacity(instructions.len());
+
+    for &instruction in instructions {
+        match (result.last(), instruction) {
+            (Some(Instruction::Clear), Instruction::AddByte(n)) => *result.last_mut().unwrap() = Instruction::SetByte(n),
+            _ => result.push(instruction),
+        }
+    }
+
+    report.set_bytes_fused += instructions.len() - result.len();
+    result
+}
+
+/// Recognizes loops like `[->++>+++<<]` that add multiples of the current cell to other cells
+fn mul_add(body: &[Instruction]) -> Option<Vec<Instruction>> {
+    let mut offset: isize = 0;
+    let mut reach = (0, 0);
+    let mut counter: u8 = 0;
+    let mut targets: Vec<(isize, u8)> = Vec::new();
+
+    for &instruction in body {
+        match instruction {
+            Instruction::MovePointer(n) => {
+                offset = offset.checked_add(n)?;
+                reach = (reach.0.min(offset), reach.1.max(offset));
+            },
+            Instruction::AddByte(n) if offset == 0 => counter = counter.wrapping_add(n),
+            Instruction::AddByte(n) => match targets.iter_mut().find(|(o, _)| *o == offset) {
+                Some((_, factor)) => *factor = factor.wrapping_add(n),
+                None => targets.push((offset, n)),
+            },
+            _ => return None,
+        }
+    }
+
+    // The loop must return to where it started and count the current cell down by one
+    if offset != 0 || counter != u8::MAX {
+        return None;
+    }
+
+    let mut collapsed: Vec<Instruction> = targets
+        .into_iter()
+        .filter(|&(_, factor)| factor != 0)
+        .map(|(offset, factor)| Instruction::MulAdd { offset, factor })
+        .collect();
+
+    // The loop fails on cells off the tape, so the farthest cells it reaches on either
+    // side must be checked by a `MulAdd`
+    let checked = |o: isize| o == 0 || collapsed.iter().any(|&v| matches!(v, Instruction::MulAdd { offset, .. } if offset == o));
+    if !checked(reach.0) || !checked(reach.1) {
+        return None;
+    }
+    collapsed.push(Instruction::Clear);
+
+    Some(collapsed)
+}
@@ -0,0 +1,228 @@
+//! Turns source text into instructions
+//!
+//! The parser is fed the source in chunks, so the same code handles a whole
+//! string at once and a large file read a block at a time. Brackets are validated
+//! with a running depth counter as the instructions are produced.
+
+use std::collections::HashMap;
+
+use crate::{BFError, Instruction};
+
+/// How source text maps to instructions
+#[derive (Clone, Copy)]
+pub(crate) enum Dialect<'a> {
+    /// Every instruction is a single char, the common case
+    Chars(&'a HashMap<char, Instruction>),
+    /// Instructions are multi-char tokens, matched longest first
+    Tokens(&'a HashMap<String, Instruction>),
+}
+
+impl Dialect<'_> {
+    /// Returns the instruction at the start of `source` and its length in bytes
+    fn next_instruction(&self, source: &str) -> Option<(Instruction, usize)> {
+        match self {
+            Dialect::Chars(map) => {
+                let c = source.chars().next()?;
+                map.get(&c).map(|&v| (v, c.len_utf8()))
+            },
+            Dialect::Tokens(map) => map
+                .iter()
+                .filter(|(token, _)| !token.is_empty() && source.starts_with(token.as_str()))
+                .max_by_key(|(token, _)| token.len())
+                .map(|(token, &v)| (v, token.len())),
+        }
+    }
+
+    /// Returns how many bytes must follow a position to be sure of the instruction there
+    fn lookahead(&self) -> usize {
+        match self {
+            Dialect::Chars(_) => 1,
+            Dialect::Tokens(map) => map.keys().map(|token| token.len()).max().unwrap_or(1),
+        }
+    }
+}
+
+pub(crate) struct Parser<'a> {
+    dialect: Dialect<'a>,
+    line_comment: Option<char>,
+    right_to_left: bool,
+    ignore_unknown_chars: bool,
+    ignored: Option<HashMap<char, usize>>,
+    lookahead: usize,
+
+    in_comment: bool,
+    depth: usize,
+    max_depth: usize,
+    index: usize,
+    instructions: Vec<Instruction>,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(
+        dialect: Dialect<'a>,
+        line_comment: Option<char>,
+        right_to_left: bool,
+        ignore_unknown_chars: bool,
+        collect_ignored: bool,
+    ) -> Self {
+        Self {
+            dialect,
+            line_comment,
+            right_to_left,
+            ignore_unknown_chars,
+            ignored: collect_ignored.then(HashMap::new),
+            lookahead: dialect.lookahead(),
+            in_comment: false,
+            depth: 0,
+            max_depth: 0,
+            index: 0,
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Parses the whole source, or the last chunk of it
+    pub(crate) fn push_str(&mut self, source: &str) -> Result<(), BFError> {
+        self.parse(source, true).map(|_| ())
+    }
+
+    /// Parses the next chunk of source, returns how many of its bytes were parsed
+    /// 
+    /// The end of the chunk may be the start of a token that continues in the next one,
+    /// so it's left unparsed. Pass it again at the start of the next chunk.
+    pub(crate) fn push_chunk(&mut self, source: &str) -> Result<usize, BFError> {
+        self.parse(source, false)
+    }
+
+    fn parse(&mut self, source: &str, last: bool) -> Result<usize, BFError> {
+        let mut rest = source;
+
+        while let Some(c) = rest.chars().next() {
+            if !last && rest.len() < self.lookahead {
+                break;
+            }
+            let index = self.index;
+
+            // Comments run until the end of the line, newline included
+            if Some(c) == self.line_comment {
+                self.in_comment = true;
+            }
+            if self.in_comment {
+                self.in_comment = c != '\n';
+                self.index += 1;
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+
+            let (instruction, len) = match self.dialect.next_instruction(rest) {
+                Some(v) => v,
+                None if self.ignore_unknown_chars => {
+                    if let (Some(ignored), false) = (self.ignored.as_mut(), c.is_whitespace()) {
+                        *ignored.entry(c).or_insert(0) += 1;
+                    }
+                    self.index += 1;
+                    rest = &rest[c.len_utf8()..];
+                    continue;
+                },
+                None => return Err(BFError::InvalidInstruction { instruction: c, index }),
+            };
+            self.index += rest[..len].chars().count();
+            rest = &rest[len..];
+
+            // Right-to-left programs are read backwards, so their loops close before they open
+            let (open, close) = match self.right_to_left {
+                false => (Instruction::OpenLoop, Instruction::CloseLoop),
+                true => (Instruction::CloseLoop, Instruction::OpenLoop),
+            };
+
+            if instruction == open {
+                self.depth += 1;
+                self.max_depth = self.max_depth.max(self.depth);
+            } else if instruction == close {
+                self.depth = self.depth.checked_sub(1).ok_or(BFError::UnbalancedBrackets)?;
+            }
+
+            self.instructions.push(instruction);
+        }
+
+        Ok(source.len() - rest.len())
+    }
+
+    /// Returns the deepest loop nesting seen so far
+    pub(crate) fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Returns how many times each skipped unknown char appeared, empty unless collected
+    pub(crate) fn take_ignored(&mut self) -> HashMap<char, usize> {
+        self.ignored.take().unwrap_or_default()
+    }
+
+    /// Returns the parsed instructions in execution order
+    pub(crate) fn finish(mut self) -> Result<Vec<Instruction>, BFError> {
+        if self.depth != 0 {
+            return Err(BFError::UnbalancedBrackets);
+        }
+
+        if self.right_to_left {
+            self.instructions.reverse();
+        }
+
+        Ok(self.instructions)
+    }
+}
+
+/// Checks that every bracket of an already built program has a match
+pub(crate) fn check_brackets(instructions: &[Instruction]) -> Result<(), BFError> {
+    let mut depth: usize = 0;
+
+    for &instruction in instructions {
+        match instruction {
+            Instruction::OpenLoop => depth += 1,
+            Instruction::CloseLoop => depth = depth.checked_sub(1).ok_or(BFError::UnbalancedBrackets)?,
+            _ => (),
+        }
+    }
+
+    match depth {
+        0 => Ok(()),
+        _ => Err(BFError::UnbalancedBrackets),
+    }
+}
+
+/// Returns the deepest loop nesting of an already built program
+pub(crate) fn nesting_depth(instructions: &[Instruction]) -> usize {
+    let mut depth: usize = 0;
+    let mut max = 0;
+
+    for &instruction in instructions {
+        match instruction {
+            Instruction::OpenLoop => {
+                depth += 1;
+                max = max.max(depth);
+            },
+            Instruction::CloseLoop => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    max
+}
+
+/// Returns the deepest loop nesting in a program written with the standard instructions
+/// 
+/// Chars other than the eight instructions are ignored
+/// 
+/// # Examples
+/// ```ignore
+/// assert_eq!(max_nesting_depth("+[>[-]<-]")?, 2);
+/// ```
+pub fn max_nesting_depth(instructions: &str) -> Result<usize, BFError> {
+    let instructions_map = crate::default_instructions();
+    let mut parser = Parser::new(Dialect::Chars(&instructions_map), None, false, true, false);
+    parser.push_str(instructions)?;
+
+    let depth = parser.max_depth();
+    parser.finish()?;
+
+    Ok(depth)
+}
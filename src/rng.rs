@@ -0,0 +1,39 @@
+//! Small seedable random number generator for the `Random` instruction
+//!
+//! SplitMix64: fast, accepts any seed including 0, and good enough for
+//! programs that want a random byte. Not suitable for cryptography.
+
+use std::{collections::hash_map::RandomState, hash::{BuildHasher, Hasher}, time::SystemTime};
+
+/// Returns a seed that differs between runs and processes
+pub(crate) fn entropy_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(v) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(v.as_nanos());
+    }
+    hasher.finish()
+}
+
+#[derive (Clone, Debug)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+}
@@ -0,0 +1,124 @@
+//! Async running that periodically yields to the executor
+//!
+//! Only available with the `async` feature. It doesn't depend on a particular
+//! runtime, yielding is done with a plain future that wakes itself once.
+
+use std::{collections::VecDeque, future::Future, pin::Pin, task::{Context, Poll}};
+
+use crate::{BFError, BFInterpreter, Instruction, OnMissingInput};
+
+/// Number of steps run between two yields when `run_async` is given 0
+const DEFAULT_YIELD_EVERY: u64 = 10_000;
+
+/// Returns `Pending` once so the executor can run other tasks
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        match self.0 {
+            true => Poll::Ready(()),
+            false => {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl BFInterpreter {
+    /// Makes `,` in `run_async` await the next byte from `callback`, `None` meaning EOF
+    /// 
+    /// Read once the input set with `set_input` is exhausted. `on_input` takes precedence,
+    /// and runs that aren't async don't use it.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let (sender, receiver) = tokio::sync::mpsc::channel(16);
+    /// let receiver = Rc::new(RefCell::new(receiver));
+    /// interpreter.on_input_async(move || {
+    ///     let receiver = receiver.clone();
+    ///     async move { receiver.borrow_mut().recv().await }
+    /// });
+    /// ```
+    pub fn on_input_async<F, Fut>(&mut self, mut callback: F)
+    where
+        F: FnMut() -> Fut + 'static,
+        Fut: Future<Output = Option<u8>> + 'static,
+    {
+        self.async_input_callback = Some(Box::new(move || Box::pin(callback())));
+    }
+
+    /// Runs the program like `try_run`, yielding to the executor every `yield_every` steps
+    /// 
+    /// Keeps a long running program from blocking other tasks on the same runtime. A
+    /// `yield_every` of 0 uses a default of 10 000 steps. Input comes from `set_input`,
+    /// `on_input` or `on_input_async`. Prompting on stdin would block the runtime, so
+    /// `OnMissingInput::Block` fails like `OnMissingInput::Error` here.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let output = interpreter.run_async(source, 1000).await?;
+    /// ```
+    pub async fn run_async(&mut self, instructions: &str, yield_every: u64) -> Result<String, BFError> {
+        let yield_every = match yield_every {
+            0 => DEFAULT_YIELD_EVERY,
+            n => n,
+        };
+
+        self.load(instructions)?;
+
+        let on_missing_input = self.on_missing_input;
+        if on_missing_input == OnMissingInput::Block {
+            self.on_missing_input = OnMissingInput::Error;
+        }
+
+        let mut steps = 0;
+        let mut result = Ok(true);
+        while let Ok(true) = result {
+            result = self.async_step().await;
+
+            steps += 1;
+            if steps % yield_every == 0 {
+                YieldNow(false).await;
+            }
+        }
+
+        self.on_missing_input = on_missing_input;
+        self.finish_run(result.map(|_| ()))?;
+        Ok(self.output_string())
+    }
+
+    /// Runs the next instruction like `step`, awaiting the input it reads first
+    async fn async_step(&mut self) -> Result<bool, BFError> {
+        match self.current_instruction() {
+            Some(Instruction::Input | Instruction::PeekInput) => self.await_input().await,
+            // Every iteration of the collapsed `[.,]` reads a byte, so they're run here
+            // and `step` finds the loop already done
+            Some(Instruction::Cat) => while self.data[self.data_pointer] != 0 {
+                self.await_input().await;
+                self.cat_iteration()?;
+            },
+            _ => (),
+        }
+
+        self.step()
+    }
+
+    /// Queues the next byte from `on_input_async` if `,` would need one
+    async fn await_input(&mut self) {
+        let available = self.input_callback.is_some()
+            || !self.undone_input.is_empty()
+            || self.input.as_ref().is_some_and(|v| !v.is_empty());
+        let callback = match (available, self.async_input_callback.as_mut()) {
+            (false, Some(v)) => v,
+            _ => return,
+        };
+
+        // An empty queue makes `,` apply the EOF behavior
+        let byte = callback().await;
+        self.input.get_or_insert_with(VecDeque::new).extend(byte);
+    }
+}
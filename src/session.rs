@@ -0,0 +1,191 @@
+//! Recorded sessions, the source of a run, the settings it depends on and the input
+//! it consumed
+//!
+//! A session file starts with a `bf-session 3` line and one `name value` line per
+//! setting, followed by the length of the source in bytes on its own line, the
+//! source itself, and then every input byte up to the end of the file. Input is
+//! stored raw so binary input round-trips. Files of older versions are still read,
+//! settings they don't store are left at their defaults.
+
+use std::{collections::HashMap, fs, io, path::Path};
+
+use crate::{BFInterpreterConfig, CellArithmetic, EofBehavior, Instruction, PointerUnderflow, DEFAULT_TAPE_SIZE};
+
+const HEADER: &str = "bf-session 3\n";
+const OLD_HEADERS: [&str; 2] = ["bf-session 1\n", "bf-session 2\n"];
+
+/// A run that can be replayed by running `source` with `config` and `input` preloaded
+/// 
+/// Every setting that changes what a program does is stored. Settings that only
+/// change how it's run, like `opt_level` or the output sinks, are left at their
+/// defaults by `config`.
+/// A tape loaded with `load_tape_from` isn't stored.
+#[derive (Clone, Debug, PartialEq)]
+pub struct Session {
+    pub source: String,
+    pub input: Vec<u8>,
+    pub eof_behavior: EofBehavior,
+    pub tape_size: usize,
+    pub fill_value: u8,
+    /// Seed the `Random` instruction was run with
+    pub rng_seed: u64,
+    pub cell_arithmetic: CellArithmetic,
+    pub pointer_underflow: PointerUnderflow,
+    pub initial_pointer: usize,
+    pub growable_tape: bool,
+    pub max_tape_size: Option<usize>,
+    pub include_input_newline: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            input: Vec::new(),
+            eof_behavior: EofBehavior::default(),
+            tape_size: DEFAULT_TAPE_SIZE,
+            fill_value: 0,
+            rng_seed: 0,
+            cell_arithmetic: CellArithmetic::default(),
+            pointer_underflow: PointerUnderflow::default(),
+            initial_pointer: 0,
+            growable_tape: false,
+            max_tape_size: None,
+            include_input_newline: false,
+        }
+    }
+}
+
+impl Session {
+    pub(crate) fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let max_tape_size = match self.max_tape_size {
+            Some(v) => v.to_string(),
+            None => "none".to_string(),
+        };
+        let mut bytes = format!(
+            "{}eof_behavior {:?}\ntape_size {}\nfill_value {}\nrng_seed {}\ncell_arithmetic {:?}\n\
+            pointer_underflow {:?}\ninitial_pointer {}\ngrowable_tape {}\nmax_tape_size {}\n\
+            include_input_newline {}\n{}\n{}",
+            HEADER,
+            self.eof_behavior,
+            self.tape_size,
+            self.fill_value,
+            self.rng_seed,
+            self.cell_arithmetic,
+            self.pointer_underflow,
+            self.initial_pointer,
+            self.growable_tape,
+            max_tape_size,
+            self.include_input_newline,
+            self.source.len(),
+            self.source,
+        ).into_bytes();
+        bytes.extend_from_slice(&self.input);
+
+        fs::write(path, bytes)
+    }
+
+    /// Reads a session written by `BFInterpreter::start_recording`
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// let session = Session::read("./bug.session")?;
+    /// let mut interpreter = BFInterpreter::new(Some(session.config()))?;
+    /// interpreter.set_input(&session.input);
+    /// interpreter.run(&session.source);
+    /// ```
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let bytes = fs::read(path)?;
+        let mut session = Session::default();
+        let mut rest = std::iter::once(HEADER)
+            .chain(OLD_HEADERS)
+            .find_map(|header| bytes.strip_prefix(header.as_bytes()))
+            .ok_or_else(|| invalid("Missing session header"))?;
+        let next_line = |rest: &mut &[u8]| {
+            let newline = rest.iter().position(|&b| b == b'\n')?;
+            let line = std::str::from_utf8(&rest[..newline]).ok()?.to_string();
+            *rest = &rest[newline + 1..];
+            Some(line)
+        };
+
+        // Settings come before the source length, the only line that's a bare number
+        let len: usize = loop {
+            let line = next_line(&mut rest).ok_or_else(|| invalid("Missing source length"))?;
+            let (name, value) = match line.split_once(' ') {
+                Some(v) => v,
+                None => break line.parse().map_err(|_| invalid("Invalid source length"))?,
+            };
+            let invalid_value = || invalid(&format!("Invalid value for {}", name));
+
+            match name {
+                "eof_behavior" => session.eof_behavior = match value {
+                    "Unchanged" => EofBehavior::Unchanged,
+                    "Zero" => EofBehavior::Zero,
+                    "MaxValue" => EofBehavior::MaxValue,
+                    _ => return Err(invalid_value()),
+                },
+                "tape_size" => session.tape_size = value.parse().map_err(|_| invalid_value())?,
+                "fill_value" => session.fill_value = value.parse().map_err(|_| invalid_value())?,
+                "rng_seed" => session.rng_seed = value.parse().map_err(|_| invalid_value())?,
+                "cell_arithmetic" => session.cell_arithmetic = match value {
+                    "Wrapping" => CellArithmetic::Wrapping,
+                    "Saturating" => CellArithmetic::Saturating,
+                    _ => return Err(invalid_value()),
+                },
+                "pointer_underflow" => session.pointer_underflow = match value {
+                    "Error" => PointerUnderflow::Error,
+                    "Clamp" => PointerUnderflow::Clamp,
+                    _ => return Err(invalid_value()),
+                },
+                "initial_pointer" => session.initial_pointer = value.parse().map_err(|_| invalid_value())?,
+                "growable_tape" => session.growable_tape = value.parse().map_err(|_| invalid_value())?,
+                "max_tape_size" => session.max_tape_size = match value {
+                    "none" => None,
+                    v => Some(v.parse().map_err(|_| invalid_value())?),
+                },
+                "include_input_newline" => session.include_input_newline = value.parse().map_err(|_| invalid_value())?,
+                _ => return Err(invalid(&format!("Unknown setting {}", name))),
+            }
+        };
+
+        if rest.len() < len {
+            return Err(invalid("Session ended inside the source"));
+        }
+
+        session.source = String::from_utf8(rest[..len].to_vec()).map_err(|_| invalid("Source is not valid UTF-8"))?;
+        session.input = rest[len..].to_vec();
+        Ok(session)
+    }
+
+    /// Returns a config that replays the session as it was recorded
+    /// 
+    /// The source is standard brainfuck with the extensions written as by `source`,
+    /// so they're mapped too, see `Instruction`'s `Display`.
+    pub fn config(&self) -> BFInterpreterConfig {
+        let mut instructions: HashMap<char, Instruction> = crate::default_instructions();
+        instructions.extend([
+            ('%', Instruction::Random),
+            ('@', Instruction::DumpTape),
+            ('!', Instruction::Signal),
+            ('$', Instruction::PrintPointer),
+            ('?', Instruction::PeekInput),
+        ]);
+
+        BFInterpreterConfig {
+            custom_instructions: Some(instructions),
+            eof_behavior: self.eof_behavior,
+            tape_size: Some(self.tape_size),
+            fill_value: self.fill_value,
+            rng_seed: Some(self.rng_seed),
+            cell_arithmetic: self.cell_arithmetic,
+            pointer_underflow: self.pointer_underflow,
+            initial_pointer: self.initial_pointer,
+            growable_tape: self.growable_tape,
+            max_tape_size: self.max_tape_size,
+            include_input_newline: self.include_input_newline,
+            ..Default::default()
+        }
+    }
+}
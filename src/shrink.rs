@@ -0,0 +1,125 @@
+//! Shrinks failing programs into small reproductions
+//!
+//! Delta debugging: chunks of the program are removed, halving the chunk size
+//! whenever nothing can go, and loops are unwrapped by removing both of their
+//! brackets. A removal is kept only if the brackets stay balanced and the
+//! predicate still holds.
+
+/// Returns the smallest program found for which `predicate` still holds
+/// 
+/// Works on the chars of `source`, so comments are shrunk away too. Brackets are `[`
+/// and `]`. The result is only a local minimum. If `predicate` doesn't hold for
+/// `source` it's returned unchanged.
+/// 
+/// Removing instructions can make a loop infinite, so the predicate should cap the
+/// steps it runs, e.g. with `max_steps`.
+/// 
+/// # Examples
+/// ```ignore
+/// // Find what makes the program panic
+/// let config = BFInterpreterConfig { max_steps: Some(1_000_000), ..Default::default() };
+/// let repro = shrink(source, |program| {
+///     let mut interpreter = BFInterpreter::new(Some(config.clone())).unwrap();
+///     std::panic::catch_unwind(AssertUnwindSafe(|| interpreter.try_run(program))).is_err()
+/// });
+/// ```
+pub fn shrink<F: FnMut(&str) -> bool>(source: &str, mut predicate: F) -> String {
+    if !predicate(source) {
+        return source.to_string();
+    }
+
+    let mut program: Vec<char> = source.chars().collect();
+    let mut chunk = (program.len() / 2).max(1);
+
+    loop {
+        let mut removed = false;
+
+        let mut start = 0;
+        while start < program.len() {
+            let end = (start + chunk).min(program.len());
+            let candidate: Vec<char> = program[..start].iter().chain(&program[end..]).copied().collect();
+
+            match balanced(&candidate) && predicate(&candidate.iter().collect::<String>()) {
+                true => {
+                    program = candidate;
+                    removed = true;
+                },
+                false => start += chunk,
+            }
+        }
+
+        let mut open = 0;
+        while open < program.len() {
+            let close = match program[open] {
+                '[' => matching_close(&program, open),
+                _ => None,
+            };
+            let close = match close {
+                Some(v) => v,
+                None => {
+                    open += 1;
+                    continue;
+                },
+            };
+
+            let candidate: String = program.iter()
+                .enumerate()
+                .filter(|&(i, _)| i != open && i != close)
+                .map(|(_, &c)| c)
+                .collect();
+
+            match predicate(&candidate) {
+                true => {
+                    program = candidate.chars().collect();
+                    removed = true;
+                },
+                false => open += 1,
+            }
+        }
+
+        match (removed, chunk) {
+            (false, 1) => break,
+            (false, _) => chunk /= 2,
+            (true, _) => (),
+        }
+    }
+
+    program.into_iter().collect()
+}
+
+fn balanced(program: &[char]) -> bool {
+    let mut depth: usize = 0;
+
+    for &c in program {
+        match c {
+            '[' => depth += 1,
+            ']' => match depth.checked_sub(1) {
+                Some(v) => depth = v,
+                None => return false,
+            },
+            _ => (),
+        }
+    }
+
+    depth == 0
+}
+
+/// Returns the index of the bracket closing the one at `open`
+fn matching_close(program: &[char], open: usize) -> Option<usize> {
+    let mut depth: usize = 0;
+
+    for (i, &c) in program.iter().enumerate().skip(open) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    None
+}
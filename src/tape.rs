@@ -0,0 +1,111 @@
+//! Storage for the cells of the tape
+//!
+//! The dense backend is a plain `Vec<u8>`, fast to index but as large as the
+//! tape. The sparse backend only stores the cells that were written, so a
+//! program touching cell 0 and cell 1 000 000 costs two entries instead of a
+//! megabyte, at the price of a hash lookup on every access.
+
+use std::{borrow::Cow, collections::HashMap, ops::{Index, IndexMut}};
+
+/// Both backends remember the `fill` value that new cells start with
+#[derive (Debug)]
+pub(crate) enum Tape {
+    Dense { cells: Vec<u8>, fill: u8 },
+    Sparse { cells: HashMap<usize, u8>, len: usize, fill: u8 },
+}
+
+impl Tape {
+    /// Creates a tape of `len` cells set to `fill`, with the first ones set to `initial`
+    pub(crate) fn new(len: usize, sparse: bool, fill: u8, initial: &[u8]) -> Self {
+        match sparse {
+            false => {
+                let mut cells = vec![fill; len];
+                cells[..initial.len()].copy_from_slice(initial);
+                Tape::Dense { cells, fill }
+            },
+            true => Tape::Sparse {
+                cells: initial.iter().copied().enumerate().filter(|&(_, v)| v != fill).collect(),
+                len,
+                fill,
+            },
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Tape::Dense { cells, .. } => cells.len(),
+            Tape::Sparse { len, .. } => *len,
+        }
+    }
+
+    /// Grows the tape with cells set to the fill value or drops the cells past `new_len`
+    pub(crate) fn resize(&mut self, new_len: usize) {
+        match self {
+            Tape::Dense { cells, fill } => cells.resize(new_len, *fill),
+            Tape::Sparse { cells, len, .. } => {
+                cells.retain(|&i, _| i < new_len);
+                *len = new_len;
+            },
+        }
+    }
+
+    /// Returns the index and value of every non-zero cell, in tape order
+    /// 
+    /// Goes through the whole tape for a sparse one with a non-zero fill value
+    pub(crate) fn non_zero(&self) -> Vec<(usize, u8)> {
+        let mut cells: Vec<(usize, u8)> = match self {
+            Tape::Dense { cells, .. } => cells.iter().copied().enumerate().collect(),
+            Tape::Sparse { cells, fill: 0, .. } => cells.iter().map(|(&i, &v)| (i, v)).collect(),
+            Tape::Sparse { cells, len, fill } => (0..*len).map(|i| (i, *cells.get(&i).unwrap_or(fill))).collect(),
+        };
+
+        cells.retain(|&(_, v)| v != 0);
+        cells.sort_unstable();
+        cells
+    }
+
+    /// Returns every cell, which allocates the whole tape for a sparse one
+    pub(crate) fn as_slice(&self) -> Cow<'_, [u8]> {
+        match self {
+            Tape::Dense { cells, .. } => Cow::Borrowed(cells),
+            Tape::Sparse { cells, len, fill } => {
+                let mut dense = vec![*fill; *len];
+                cells.iter().for_each(|(&i, &v)| dense[i] = v);
+                Cow::Owned(dense)
+            },
+        }
+    }
+}
+
+/// Tapes are equal when they have the same length and cells, whatever their backend
+impl PartialEq for Tape {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.non_zero() == other.non_zero()
+    }
+}
+
+impl Index<usize> for Tape {
+    type Output = u8;
+
+    fn index(&self, index: usize) -> &u8 {
+        match self {
+            Tape::Dense { cells, .. } => &cells[index],
+            Tape::Sparse { cells, len, fill } => {
+                assert!(index < *len, "Cell {} is outside of a tape of {} cells", index, len);
+                cells.get(&index).unwrap_or(fill)
+            },
+        }
+    }
+}
+
+impl IndexMut<usize> for Tape {
+    fn index_mut(&mut self, index: usize) -> &mut u8 {
+        match self {
+            Tape::Dense { cells, .. } => &mut cells[index],
+            Tape::Sparse { cells, len, fill } => {
+                assert!(index < *len, "Cell {} is outside of a tape of {} cells", index, len);
+                cells.entry(index).or_insert(*fill)
+            },
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Renders the tape as a grayscale image
+//!
+//! Only available with the `tape-image` feature. It only produces pixel values,
+//! displaying or encoding them is up to the caller.
+
+use crate::BFInterpreter;
+
+impl BFInterpreter {
+    /// Returns the first `width * height` cells as 8-bit grayscale pixels, row by row
+    /// 
+    /// Every cell is one pixel whose brightness is its value, so empty cells are black.
+    /// Pixels past the end of the tape are black too. A `height` of 1 draws the tape
+    /// as a strip.
+    /// 
+    /// # Examples
+    /// ```ignore
+    /// interpreter.run("+++[>+++++<-]")?;
+    /// let pixels = interpreter.tape_image(32, 32);
+    /// ```
+    pub fn tape_image(&self, width: usize, height: usize) -> Vec<u8> {
+        let pixels = width.saturating_mul(height);
+        let cells = pixels.min(self.data.len());
+
+        let mut image: Vec<u8> = (0..cells).map(|i| self.data[i]).collect();
+        image.resize(pixels, 0);
+        image
+    }
+}
@@ -0,0 +1,53 @@
+//! Pausing programs with `run_until_breakpoint`
+
+use brainfuck_interpreter_rs::BFInterpreter;
+
+#[test]
+fn hit_count_breakpoint_stops_on_the_nth_execution() {
+    // Prints 5, 4, 3, 2, 1, the output is at index 13
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    interpreter.load("+++++[>+<-]>[.-]").unwrap();
+    interpreter.add_hit_count_breakpoint(13, 3);
+
+    assert!(interpreter.run_until_breakpoint().unwrap());
+    assert_eq!(interpreter.instruction_pointer(), 13);
+    assert_eq!(interpreter.output_bytes(), [5, 4]);
+
+    // Hit once, so the rest of the program runs through
+    assert!(!interpreter.run_until_breakpoint().unwrap());
+    assert_eq!(interpreter.output_bytes(), [5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn hit_count_breakpoint_counts_the_first_instruction() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    interpreter.load("+.").unwrap();
+    interpreter.add_hit_count_breakpoint(0, 1);
+
+    assert!(interpreter.run_until_breakpoint().unwrap());
+    assert_eq!(interpreter.instruction_pointer(), 0);
+    assert_eq!(interpreter.steps(), 0);
+
+    assert!(!interpreter.run_until_breakpoint().unwrap());
+    assert_eq!(interpreter.output_bytes(), [1]);
+}
+
+#[test]
+fn hit_count_breakpoint_counts_hits_stopped_by_other_breakpoints() {
+    // The output at index 13 runs 5 times, the condition stops on its first 2
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    interpreter.load("+++++[>+<-]>[.-]").unwrap();
+    interpreter.add_conditional_breakpoint(|bf| bf.instruction_pointer() == 13 && bf.output_bytes().len() < 2);
+    interpreter.add_hit_count_breakpoint(13, 3);
+
+    for printed in [0, 1] {
+        assert!(interpreter.run_until_breakpoint().unwrap());
+        assert_eq!(interpreter.output_bytes().len(), printed);
+    }
+
+    assert!(interpreter.run_until_breakpoint().unwrap());
+    assert_eq!(interpreter.output_bytes(), [5, 4]);
+
+    assert!(!interpreter.run_until_breakpoint().unwrap());
+    assert_eq!(interpreter.output_bytes(), [5, 4, 3, 2, 1]);
+}
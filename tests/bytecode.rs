@@ -0,0 +1,99 @@
+//! Running crafted bytecode, which may hold offsets no parsed program produces
+
+use brainfuck_interpreter_rs::{expand, from_bytecode, BFError, BFInterpreter, BFInterpreterConfig, Instruction, OptLevel, MAX_BYTECODE_OFFSET};
+
+/// Bytecode with a header for `count` instructions followed by `body`
+fn bytecode(count: u32, body: &[u8]) -> Vec<u8> {
+    let mut bytes = b"BFBC\x02".to_vec();
+    bytes.extend(count.to_le_bytes());
+    bytes.extend(body);
+    bytes
+}
+
+#[test]
+fn programs_round_trip_through_bytecode() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        opt_level: OptLevel::Full,
+        ..Default::default()
+    })).unwrap();
+    interpreter.set_input(b"");
+    let output = interpreter.run("++++++++[>++++++++<-]>+.[-]<,[.,]>>+[<]>[->++>+++<<]");
+
+    let instructions = from_bytecode(&interpreter.to_bytecode().unwrap()).unwrap();
+    assert_eq!(instructions, interpreter.instructions());
+
+    interpreter.set_input(b"");
+    assert_eq!(interpreter.run_compiled(&instructions).unwrap(), output);
+}
+
+#[test]
+fn malformed_bytecode_is_rejected() {
+    assert_eq!(from_bytecode(b"BFB"), Err(BFError::InvalidBytecodeHeader));
+    assert_eq!(from_bytecode(b"BFBX\x02\0\0\0\0"), Err(BFError::InvalidBytecodeHeader));
+    assert_eq!(from_bytecode(b"BFBC\x01\0\0\0\0"), Err(BFError::UnsupportedBytecodeVersion(1)));
+
+    // Two instructions announced, the `MovePointer` offset is cut short
+    assert_eq!(from_bytecode(&bytecode(2, &[0, 9, 1, 0])), Err(BFError::TruncatedBytecode));
+    assert_eq!(from_bytecode(&bytecode(1, &[0, 1])), Err(BFError::InvalidBytecodeLength { expected: 10, found: 11 }));
+    assert_eq!(from_bytecode(&bytecode(1, &[42])), Err(BFError::InvalidOpcode { opcode: 42, offset: 9 }));
+}
+
+#[test]
+fn huge_offsets_fail_instead_of_overflowing() {
+    let instructions = [Instruction::PointerInc, Instruction::MovePointer(isize::MAX)];
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    assert_eq!(
+        interpreter.run_compiled(&instructions),
+        Err(BFError::PointerOutOfBounds { pointer: isize::MAX, index: 1 }),
+    );
+
+    let mul_add = [Instruction::ByteInc, Instruction::MulAdd { offset: isize::MIN, factor: 1 }];
+    assert!(matches!(interpreter.run_compiled(&mul_add), Err(BFError::PointerOutOfBounds { .. })));
+}
+
+#[test]
+fn huge_offsets_are_not_merged_past_their_limit() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        opt_level: OptLevel::Basic,
+        ..Default::default()
+    })).unwrap();
+
+    let instructions = [Instruction::MovePointer(isize::MIN), Instruction::PointerDec];
+    assert_eq!(
+        interpreter.run_compiled(&instructions),
+        Err(BFError::PointerOutOfBounds { pointer: isize::MIN, index: 0 }),
+    );
+}
+
+#[test]
+fn offsets_past_the_limit_are_rejected() {
+    let mut body = vec![12];
+    body.extend(i64::MIN.to_le_bytes());
+    body.push(1);
+    assert_eq!(from_bytecode(&bytecode(1, &body)), Err(BFError::BytecodeOffsetOutOfRange { value: i64::MIN, offset: 10 }));
+
+    for opcode in [9, 11] {
+        let mut body = vec![0, opcode];
+        body.extend((MAX_BYTECODE_OFFSET as i64 + 1).to_le_bytes());
+        assert_eq!(
+            from_bytecode(&bytecode(2, &body)),
+            Err(BFError::BytecodeOffsetOutOfRange { value: MAX_BYTECODE_OFFSET as i64 + 1, offset: 11 }),
+        );
+    }
+
+    let mut body = vec![9];
+    body.extend((-MAX_BYTECODE_OFFSET as i64).to_le_bytes());
+    assert_eq!(from_bytecode(&bytecode(1, &body)), Ok(vec![Instruction::MovePointer(-MAX_BYTECODE_OFFSET)]));
+}
+
+#[test]
+fn expanding_negative_offsets_mirrors_the_move_back() {
+    let expanded = expand(&[Instruction::MulAdd { offset: -2, factor: 1 }]);
+    assert_eq!(expanded, [
+        Instruction::PointerDec,
+        Instruction::PointerDec,
+        Instruction::ByteInc,
+        Instruction::PointerInc,
+        Instruction::PointerInc,
+    ]);
+}
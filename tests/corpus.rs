@@ -0,0 +1,80 @@
+//! Runs every program in `tests/programs` and compares its output with the
+//! expected one.
+//!
+//! A program `name.bf` (or `name.b`) is paired with `name.out`, holding the
+//! exact expected output bytes, and optionally `name.in`, holding its input.
+//! `,` reads 0 once the input is exhausted. Add a regression case by dropping
+//! the files in the directory. Programs too slow for every run go in the `slow`
+//! subdirectory, which is skipped here.
+
+use std::{fs, path::{Path, PathBuf}};
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, EofBehavior, OptLevel};
+
+const PROGRAMS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/programs");
+const MAX_STEPS: u64 = 10_000_000;
+
+fn programs() -> Vec<PathBuf> {
+    let mut programs: Vec<PathBuf> = fs::read_dir(PROGRAMS_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("b" | "bf")))
+        .collect();
+
+    programs.sort();
+    programs
+}
+
+fn run(source: &str, input: &[u8], opt_level: OptLevel) -> Result<Vec<u8>, String> {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        opt_level,
+        max_steps: Some(MAX_STEPS),
+        eof_behavior: EofBehavior::Zero,
+        ..Default::default()
+    })).map_err(|e| e.to_string())?;
+    interpreter.set_input(input);
+
+    interpreter.run_bytes(source).map_err(|e| e.to_string())
+}
+
+/// Describes where the output first differs from the expected one
+fn diff(expected: &[u8], found: &[u8]) -> String {
+    let at = expected.iter().zip(found).position(|(a, b)| a != b)
+        .unwrap_or(expected.len().min(found.len()));
+
+    format!(
+        "first difference at byte {}\n  expected: {:?}\n     found: {:?}",
+        at,
+        String::from_utf8_lossy(&expected[at..]),
+        String::from_utf8_lossy(&found[at..]),
+    )
+}
+
+fn check(program: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(program).map_err(|e| e.to_string())?;
+    let expected = fs::read(program.with_extension("out"))
+        .map_err(|e| format!("missing expected output: {}", e))?;
+    let input = fs::read(program.with_extension("in")).unwrap_or_default();
+
+    for opt_level in [OptLevel::None, OptLevel::Full] {
+        let output = run(&source, &input, opt_level).map_err(|e| format!("{:?}: {}", opt_level, e))?;
+
+        if output != expected {
+            return Err(format!("{:?}: {}", opt_level, diff(&expected, &output)));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn corpus_programs_produce_expected_output() {
+    let programs = programs();
+    assert!(!programs.is_empty(), "no programs found in {}", PROGRAMS_DIR);
+
+    let failures: Vec<String> = programs.iter()
+        .filter_map(|program| check(program).err().map(|e| format!("{}: {}", program.display(), e)))
+        .collect();
+
+    assert!(failures.is_empty(), "{} of {} programs failed:\n{}", failures.len(), programs.len(), failures.join("\n"));
+}
@@ -0,0 +1,132 @@
+//! Parses dialect definitions and runs programs written in them
+
+use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+use brainfuck_interpreter_rs::{parse_dialect, BFError, BFInterpreter, BFInterpreterConfig, EofBehavior, Instruction};
+
+const WASD: &str = "
+# Moves
+D = PointerInc
+A = PointerDec
+W = ByteInc
+S = ByteDec
+
+O = Output # Prints the current cell
+I = Input
+'(' = OpenLoop
+')' = CloseLoop
+'#' = Random
+";
+
+#[test]
+fn dialect_file_runs_programs() {
+    let map = parse_dialect(WASD).unwrap();
+    assert_eq!(map.get(&'#'), Some(&Instruction::Random));
+
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(map),
+        ..Default::default()
+    })).unwrap();
+
+    assert_eq!(interpreter.run("WWWDWWWA(DOAS)"), "\u{3}\u{3}\u{3}");
+}
+
+#[test]
+fn invalid_dialects_are_rejected() {
+    assert_eq!(parse_dialect(&WASD.replace("'#' = Random", "X = Jump")), Err(BFError::InvalidDialect {
+        line: 12,
+        reason: "unknown instruction `Jump`".to_string(),
+    }));
+    assert_eq!(parse_dialect(&WASD.replace("I = Input", "W = Input")), Err(BFError::DuplicateDialectChar('W')));
+    assert_eq!(parse_dialect(&WASD.replace("I = Input", "")), Err(BFError::MissingDialectInstruction(Instruction::Input)));
+    assert!(matches!(parse_dialect("D PointerInc"), Err(BFError::InvalidDialect { line: 1, .. })));
+
+    let escaped = WASD.replace("O = Output", "'\\n' = Output").replace("I = Input", "'\\t' = Input");
+    let map = parse_dialect(&escaped).unwrap();
+    assert_eq!((map.get(&'\n'), map.get(&'\t')), (Some(&Instruction::Output), Some(&Instruction::Input)));
+    assert!(matches!(parse_dialect(&WASD.replace("I = Input", "'\\x' = Input")), Err(BFError::InvalidDialect { line: 9, .. })));
+}
+
+#[test]
+fn signal_calls_the_callback() {
+    let map = parse_dialect(&format!("{}\n! = Signal", WASD)).unwrap();
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(map),
+        ..Default::default()
+    })).unwrap();
+
+    let signals = Rc::new(Cell::new(0));
+    let counter = signals.clone();
+    interpreter.on_signal(move || counter.set(counter.get() + 1));
+
+    assert_eq!(interpreter.run("WWW(!S)!O"), "\0");
+    assert_eq!(signals.get(), 4);
+}
+
+#[test]
+fn print_pointer_outputs_the_pointer_in_decimal() {
+    let map = parse_dialect(&format!("{}\n$ = PrintPointer", WASD)).unwrap();
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(map),
+        ..Default::default()
+    })).unwrap();
+
+    assert_eq!(interpreter.run("$DDDDDDDDDDDD$A$"), "01211");
+}
+
+#[test]
+fn peek_input_reads_without_consuming() {
+    let config = || BFInterpreterConfig {
+        custom_instructions: Some(parse_dialect(&format!("{}\n? = PeekInput", WASD)).unwrap()),
+        eof_behavior: EofBehavior::Zero,
+        ..Default::default()
+    };
+
+    let mut interpreter = BFInterpreter::new(Some(config())).unwrap();
+    interpreter.set_input(b"ab");
+    assert_eq!(interpreter.run_bytes("?O?OIOIO?O").unwrap(), b"aaab\0");
+
+    // The callback is called once per byte, however many times it's peeked
+    let calls = Rc::new(Cell::new(0u8));
+    let counter = calls.clone();
+    let mut interpreter = BFInterpreter::new(Some(config())).unwrap();
+    interpreter.on_input(move || {
+        counter.set(counter.get() + 1);
+        Some(b'0' + counter.get())
+    });
+    assert_eq!(interpreter.run_bytes("?O?OIOIO").unwrap(), b"1112");
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn whitespace_can_be_mapped_to_instructions() {
+    let whitespace = || HashMap::from([
+        (' ', Instruction::ByteInc),
+        ('\t', Instruction::ByteDec),
+        ('\n', Instruction::Output),
+        ('>', Instruction::PointerInc),
+        ('<', Instruction::PointerDec),
+        ('[', Instruction::OpenLoop),
+        (']', Instruction::CloseLoop),
+        (',', Instruction::Input),
+    ]);
+    // 8 * 8 + 1 = 65, printed twice
+    let source = "        [>        <\t]> \n\n";
+
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(whitespace()),
+        ..Default::default()
+    })).unwrap();
+    assert_eq!(interpreter.run(source), "AA");
+
+    // Read from a reader, every newline is still an instruction
+    assert_eq!(interpreter.run_from_reader(source.as_bytes()).unwrap(), "AA");
+
+    // Without ignoring unknown chars, mapped whitespace is still accepted
+    let mut strict = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(whitespace()),
+        ignore_unknown_chars: false,
+        ..Default::default()
+    })).unwrap();
+    assert_eq!(strict.try_run(source).unwrap(), "AA");
+}
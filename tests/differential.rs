@@ -0,0 +1,177 @@
+//! Runs the same programs with and without optimizations and checks that
+//! both produce the same output and leave the tape in the same state, and
+//! does the same for the two dispatch strategies, the two tape backends and
+//! optimized programs expanded back into primitive instructions.
+
+use brainfuck_interpreter_rs::{expand, BFError, BFInterpreter, BFInterpreterConfig, Dispatch, OptLevel};
+
+const PROGRAMS: &[(&str, &[u8])] = &[
+    // Hello World
+    ("++++++++++[>+++++++>++++++++++>+++>+<<<<-]>++.>+.+++++++..+++.>++.<<+++++++++++++++.>.+++.------.--------.>+.>.", b""),
+    // Hearts
+    ("+++>+++<[>.<-]", b""),
+    // Nested loops
+    ("++[>++[>+<-]<-]>>.", b""),
+    ("+++[>+++[>+++[>+<-]<-]<-]>>>.", b""),
+    // Loop skipped on a zero cell
+    ("[+.]+++[>++<-]>.", b""),
+    ("+[>[-]<-]>.", b""),
+    // Clear loops
+    ("+++++[-].>+++[+].", b""),
+    // Clear loops followed by a run, fused into a single set
+    ("+++++[-]+++++.>[-]---.", b""),
+    (">++++[<++>-]<[-]++++++++.", b""),
+    (">+++[<++>-]+++++.<.", b""),
+    // Scan loops
+    (">+>+>+>+[<]>.", b""),
+    ("+>>+<<[>>]<.", b""),
+    // Mul-add loops, forwards and backwards
+    (">>+++++[>+++++<-]>[>++>+++<<-]>.>.>.", b""),
+    (">>++++[<+++>-]<.", b""),
+    // Loops that step by more than one
+    ("--[-->+<]>.", b""),
+    // Runs that cancel out
+    ("+++--->><<+-.", b""),
+    // Input
+    (",[.,]", b"cat\0"),
+    (",>,<[->+<]>.", b"\x07\x05"),
+    (",[>,[>+<-]<-]>>.", b"\x03\x04\x05\x06"),
+];
+
+/// Programs that fail on a tape of 8 cells, some also when it can grow to 16
+const FAILING_PROGRAMS: &[&str] = &[
+    // Moves that cancel out but leave the tape on the way
+    "<>+.",
+    ">>>>>>>>>>>>>>>>><<<<<<<<<<<<<<<<<+.",
+    // Past the right end after moving right and back left
+    "+.>>>>>>>>>>>>>>>>>>>>.<<<<<<<<<<<<.",
+    // Mul-add loops that reach cells they don't change
+    "+[-<>]",
+    ">>>>>>>+[->+-<]",
+    "+[-<<+>>]",
+    // Scan loops off either end
+    "+[<]",
+    "+>+>+>+>+>+>+>+[>]",
+];
+
+fn run(source: &str, input: &[u8], opt_level: OptLevel) -> (String, Vec<u8>, usize) {
+    run_with(source, input, BFInterpreterConfig { opt_level, ..Default::default() })
+}
+
+fn run_with(source: &str, input: &[u8], config: BFInterpreterConfig) -> (String, Vec<u8>, usize) {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        tape_size: Some(64),
+        ..config
+    })).unwrap();
+    interpreter.set_input(input);
+
+    let output = interpreter.run(source);
+
+    (output, interpreter.tape().to_vec(), interpreter.data_pointer())
+}
+
+#[test]
+fn optimized_runs_match_unoptimized_runs() {
+    for &(source, input) in PROGRAMS {
+        let expected = run(source, input, OptLevel::None);
+
+        for opt_level in [OptLevel::Basic, OptLevel::Full] {
+            assert_eq!(run(source, input, opt_level), expected, "{:?} diverged on {}", opt_level, source);
+        }
+    }
+}
+
+#[test]
+fn table_dispatch_matches_match_dispatch() {
+    for &(source, input) in PROGRAMS {
+        for opt_level in [OptLevel::None, OptLevel::Basic, OptLevel::Full] {
+            let expected = run(source, input, opt_level);
+            let found = run_with(source, input, BFInterpreterConfig {
+                opt_level,
+                dispatch: Dispatch::Table,
+                ..Default::default()
+            });
+
+            assert_eq!(found, expected, "table dispatch diverged at {:?} on {}", opt_level, source);
+        }
+    }
+}
+
+#[test]
+fn sparse_tape_matches_dense_tape() {
+    for &(source, input) in PROGRAMS {
+        for opt_level in [OptLevel::None, OptLevel::Full] {
+            let expected = run(source, input, opt_level);
+            let found = run_with(source, input, BFInterpreterConfig {
+                opt_level,
+                sparse_tape: true,
+                ..Default::default()
+            });
+
+            assert_eq!(found, expected, "sparse tape diverged at {:?} on {}", opt_level, source);
+        }
+    }
+}
+
+#[test]
+fn fill_value_matches_between_tape_backends() {
+    for source in ["+>-->[-]>.", "[->+<]>.", ">>>[-<+>]<[-<+>]<."] {
+        for opt_level in [OptLevel::None, OptLevel::Full] {
+            let config = || BFInterpreterConfig { opt_level, fill_value: 7, ..Default::default() };
+            let expected = run_with(source, b"", config());
+            let found = run_with(source, b"", BFInterpreterConfig { sparse_tape: true, ..config() });
+
+            assert_eq!(expected.1[10..], [7; 54], "fill value not applied at {:?} on {}", opt_level, source);
+            assert_eq!(found, expected, "sparse tape diverged at {:?} on {}", opt_level, source);
+        }
+    }
+}
+
+#[test]
+fn expanded_programs_match_original_programs() {
+    for &(source, input) in PROGRAMS {
+        let expected = run(source, input, OptLevel::None);
+
+        let mut optimized = BFInterpreter::new(Some(BFInterpreterConfig { opt_level: OptLevel::Full, ..Default::default() })).unwrap();
+        optimized.load(source).unwrap();
+
+        let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig { tape_size: Some(64), ..Default::default() })).unwrap();
+        interpreter.set_input(input);
+        let output = interpreter.run_compiled(&expand(optimized.instructions())).unwrap();
+
+        assert_eq!((output, interpreter.tape().to_vec(), interpreter.data_pointer()), expected, "expanded program diverged on {}", source);
+    }
+}
+
+#[test]
+fn nested_loops_produce_expected_output() {
+    assert_eq!(run("++[>++[>+<-]<-]>>.", b"", OptLevel::None).0, "\u{4}");
+    assert_eq!(run(",[>,[>+<-]<-]>>.", b"\x03\x04\x05\x06", OptLevel::None).0, "\u{f}");
+}
+
+#[test]
+fn failing_programs_fail_the_same_at_every_opt_level() {
+    let run = |source: &str, opt_level, growable_tape| {
+        let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+            opt_level,
+            tape_size: Some(8),
+            growable_tape,
+            max_tape_size: Some(16),
+            ..Default::default()
+        })).unwrap();
+
+        let result = interpreter.try_run(source).map(|_| ()).map_err(|e| std::mem::discriminant::<BFError>(&e));
+        (result, interpreter.output_bytes().to_vec())
+    };
+
+    for &source in FAILING_PROGRAMS {
+        for growable_tape in [false, true] {
+            let expected = run(source, OptLevel::None, growable_tape);
+            assert!(growable_tape || expected.0.is_err(), "{} didn't fail", source);
+
+            for opt_level in [OptLevel::Basic, OptLevel::Full] {
+                assert_eq!(run(source, opt_level, growable_tape), expected, "{:?} diverged on {}", opt_level, source);
+            }
+        }
+    }
+}
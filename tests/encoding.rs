@@ -0,0 +1,39 @@
+//! Decodes the output of a program printing UTF-8 and binary bytes with every encoding
+
+use std::{cell::RefCell, rc::Rc};
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, EofBehavior, OutputEncoding};
+
+/// Echoes its input, so the output is exactly the given bytes
+const CAT: &str = ",[.,]";
+
+fn run(encoding: OutputEncoding, bytes: &[u8]) -> (String, Vec<String>) {
+    let config = || BFInterpreterConfig { output_encoding: encoding, eof_behavior: EofBehavior::Zero, ..Default::default() };
+
+    let mut interpreter = BFInterpreter::new(Some(config())).unwrap();
+    interpreter.set_input(bytes);
+    let output = interpreter.run(CAT);
+
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let sink = lines.clone();
+    let mut interpreter = BFInterpreter::new(Some(config())).unwrap();
+    interpreter.on_line(move |line| sink.borrow_mut().push(line.to_string()));
+    interpreter.set_input(bytes);
+    interpreter.run(CAT);
+    interpreter.flush_line();
+
+    let lines = lines.borrow().clone();
+    (output, lines)
+}
+
+#[test]
+fn output_is_decoded_with_the_configured_encoding() {
+    let bytes = "héllo\n\\€".as_bytes();
+
+    assert_eq!(run(OutputEncoding::Latin1, bytes), ("hÃ©llo\n\\â\u{82}¬".to_string(), vec!["hÃ©llo".to_string(), "\\â\u{82}¬".to_string()]));
+    assert_eq!(run(OutputEncoding::Utf8, bytes), ("héllo\n\\€".to_string(), vec!["héllo".to_string(), "\\€".to_string()]));
+    assert_eq!(run(OutputEncoding::Raw, bytes), ("h\\xC3\\xA9llo\n\\\\\\xE2\\x82\\xAC".to_string(), vec!["h\\xC3\\xA9llo".to_string(), "\\\\\\xE2\\x82\\xAC".to_string()]));
+
+    // Invalid UTF-8 is replaced rather than rejected
+    assert_eq!(run(OutputEncoding::Utf8, b"a\xFFb").0, "a\u{FFFD}b");
+}
@@ -0,0 +1,31 @@
+//! Finding out whether a program depends on the EOF behavior
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, EofSensitivity, OutputSink};
+
+#[test]
+fn output_is_compared_whatever_the_sink() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    assert_eq!(interpreter.detect_eof_sensitivity(",.", b""), Ok(EofSensitivity::Sensitive));
+    assert_eq!(interpreter.detect_eof_sensitivity(",.", b"a"), Ok(EofSensitivity::Insensitive));
+
+    interpreter.on_output(|_| ());
+    assert_eq!(interpreter.detect_eof_sensitivity(",.", b""), Ok(EofSensitivity::Sensitive));
+
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        output_sink: OutputSink::Null,
+        tail_output: Some(0),
+        ..Default::default()
+    })).unwrap();
+    assert_eq!(interpreter.detect_eof_sensitivity(",.", b""), Ok(EofSensitivity::Sensitive));
+}
+
+#[test]
+fn final_tape_and_pointer_are_compared() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+
+    // Nothing is printed, only the tape differs
+    assert_eq!(interpreter.detect_eof_sensitivity(",", b""), Ok(EofSensitivity::Sensitive));
+    // Only where the pointer ends up differs
+    assert_eq!(interpreter.detect_eof_sensitivity(",[[-]>]", b""), Ok(EofSensitivity::Sensitive));
+    assert_eq!(interpreter.detect_eof_sensitivity(",[-]", b""), Ok(EofSensitivity::Insensitive));
+}
@@ -0,0 +1,31 @@
+//! Drives the interpreter through its C interface, only built with the `ffi` feature
+
+#![cfg(feature = "ffi")]
+
+use brainfuck_interpreter_rs::ffi::*;
+
+fn output(interpreter: *const brainfuck_interpreter_rs::BFInterpreter) -> Vec<u8> {
+    let mut len = 0;
+    unsafe {
+        let bytes = bf_interpreter_output(interpreter, &mut len);
+        std::slice::from_raw_parts(bytes, len).to_vec()
+    }
+}
+
+#[test]
+fn c_interface_runs_programs() {
+    let interpreter = bf_interpreter_new();
+    assert!(!interpreter.is_null());
+
+    unsafe {
+        assert_eq!(bf_interpreter_run(interpreter, c",[.,]".as_ptr(), b"cat".as_ptr(), 3), BF_OK);
+        assert_eq!(output(interpreter), b"cat");
+
+        assert_eq!(bf_interpreter_run(interpreter, c"+.[".as_ptr(), std::ptr::null(), 0), BF_ERROR);
+        assert_eq!(bf_interpreter_run(interpreter, std::ptr::null(), std::ptr::null(), 0), BF_INVALID_ARGUMENT);
+        assert_eq!(bf_interpreter_run(interpreter, c"".as_ptr(), std::ptr::null(), 1), BF_INVALID_ARGUMENT);
+
+        bf_interpreter_free(interpreter);
+        bf_interpreter_free(std::ptr::null_mut());
+    }
+}
@@ -0,0 +1,48 @@
+//! The hearts example from the README and the demo binary.
+
+use brainfuck_interpreter_rs::{bf_dialect, BFInterpreter, BFInterpreterConfig, OptLevel};
+
+const HEARTS: &str = "+++>+++<[>.<-]";
+const WASD_HEARTS: &str = "WWWDWWWA(DOAS)";
+
+#[test]
+fn hearts_example_prints_three_hearts() {
+    for opt_level in [OptLevel::None, OptLevel::Basic, OptLevel::Full] {
+        let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+            opt_level,
+            ..Default::default()
+        })).unwrap();
+
+        // 3 is the heart in code page 437
+        assert_eq!(interpreter.run_bytes(HEARTS).unwrap(), [3, 3, 3], "{:?}", opt_level);
+        assert_eq!(interpreter.tape()[..2], [0, 3]);
+    }
+}
+
+#[test]
+fn wasd_hearts_match_default_hearts() {
+    let wasd = bf_dialect! {
+        'D' => PointerInc,
+        'A' => PointerDec,
+        'W' => ByteInc,
+        'S' => ByteDec,
+        'O' => Output,
+        'I' => Input,
+        '(' => OpenLoop,
+        ')' => CloseLoop,
+    };
+
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    let mut custom_interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        tape_size: Some(100),
+        custom_instructions: Some(wasd),
+        ..Default::default()
+    })).unwrap();
+
+    let expected = interpreter.run_bytes(HEARTS).unwrap();
+    assert_eq!(custom_interpreter.run_bytes(WASD_HEARTS).unwrap(), expected);
+    assert_eq!(custom_interpreter.tape()[..2], interpreter.tape()[..2]);
+
+    // The default instructions are comments in the custom dialect
+    assert_eq!(custom_interpreter.run_bytes(HEARTS).unwrap(), []);
+}
@@ -0,0 +1,46 @@
+//! Warnings for common mistakes, which don't stop programs from running
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, Warning};
+
+fn interpreter() -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        lint: true,
+        ..Default::default()
+    })).unwrap()
+}
+
+#[test]
+fn reports_common_mistakes() {
+    let mut interpreter = interpreter();
+
+    // Indices count instructions only, the comment is skipped
+    interpreter.load("[>+<-]- wraps ><[-]-").unwrap();
+    assert_eq!(interpreter.lint(), [
+        Warning::LoopNeverEntered(0),
+        Warning::DecrementOnZero(6),
+        Warning::CancellingMoves(7),
+        Warning::DecrementOnZero(12),
+    ]);
+    assert_eq!(interpreter.lint()[2].index(), 7);
+
+    // Still a valid program
+    assert_eq!(interpreter.run_bytes("[>+<-]-><[-]-").unwrap(), []);
+}
+
+#[test]
+fn correct_programs_have_no_warnings() {
+    let mut interpreter = interpreter();
+
+    for source in ["++++++++[>++++++++<-]>+.", ",[.,]", "+[->+<]>[-<+>]<-"] {
+        interpreter.load(source).unwrap();
+        assert_eq!(interpreter.lint(), [], "{}", source);
+    }
+}
+
+#[test]
+fn lint_is_empty_unless_enabled() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    interpreter.load("-<>").unwrap();
+
+    assert_eq!(interpreter.lint(), []);
+}
@@ -0,0 +1,33 @@
+//! Listing the loops of a program
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, OptLevel};
+
+#[test]
+fn lists_nested_and_sibling_loops() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        opt_level: OptLevel::None,
+        ..Default::default()
+    })).unwrap();
+
+    interpreter.load("+[>[-]<-][>]").unwrap();
+    assert_eq!(interpreter.loops(), [(1, 8), (3, 5), (9, 11)]);
+
+    for (open, close) in interpreter.loops() {
+        assert_eq!(interpreter.matching_bracket(open), Some(close));
+        assert_eq!(interpreter.matching_bracket(close), Some(open));
+    }
+
+    interpreter.load("+++.").unwrap();
+    assert_eq!(interpreter.loops(), []);
+}
+
+#[test]
+fn collapsed_loops_are_not_listed() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        opt_level: OptLevel::Full,
+        ..Default::default()
+    })).unwrap();
+
+    interpreter.load("+[,[-]]").unwrap();
+    assert_eq!(interpreter.loops().len(), 1);
+}
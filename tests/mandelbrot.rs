@@ -0,0 +1,26 @@
+//! Renders the Mandelbrot set, the usual stress test for interpreters
+//!
+//! The program takes several seconds even in a release build, so the test is
+//! ignored by default. Run it with `cargo test --release --test mandelbrot -- --ignored`.
+
+use std::{fs, path::Path};
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, OptLevel};
+
+const PROGRAM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/programs/slow/mandelbrot.b");
+
+#[test]
+#[ignore = "slow"]
+fn mandelbrot_renders_the_reference_image() {
+    let source = fs::read_to_string(PROGRAM).unwrap();
+    let expected = fs::read_to_string(Path::new(PROGRAM).with_extension("out")).unwrap();
+
+    for opt_level in [OptLevel::None, OptLevel::Full] {
+        let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+            opt_level,
+            ..Default::default()
+        })).unwrap();
+
+        assert_eq!(interpreter.try_run(&source), Ok(expected.clone()), "{:?}", opt_level);
+    }
+}
@@ -0,0 +1,32 @@
+//! What `,` does when no input was set
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig, EofBehavior, OnMissingInput};
+
+fn config(block_on_input: bool, on_missing_input: OnMissingInput) -> BFInterpreterConfig {
+    BFInterpreterConfig {
+        eof_behavior: EofBehavior::MaxValue,
+        block_on_input,
+        on_missing_input,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn missing_input_fails_by_default() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    assert_eq!(interpreter.try_run("+,."), Err(BFError::NoInput(1)));
+}
+
+#[test]
+fn not_blocking_on_input_applies_the_eof_behavior() {
+    for on_missing_input in [OnMissingInput::Error, OnMissingInput::Eof, OnMissingInput::Block] {
+        let mut interpreter = BFInterpreter::new(Some(config(false, on_missing_input))).unwrap();
+        assert_eq!(interpreter.run_bytes(",."), Ok(vec![u8::MAX]), "{:?}", on_missing_input);
+    }
+}
+
+#[test]
+fn eof_policy_applies_the_eof_behavior() {
+    let mut interpreter = BFInterpreter::new(Some(config(true, OnMissingInput::Eof))).unwrap();
+    assert_eq!(interpreter.run_bytes(",."), Ok(vec![u8::MAX]));
+}
@@ -0,0 +1,31 @@
+//! Runs deeply nested programs to make sure parsing, optimizing and running never
+//! recurse once per loop, which would overflow the stack of the test thread
+
+use brainfuck_interpreter_rs::{max_nesting_depth, BFInterpreter, BFInterpreterConfig, Dispatch, OptLevel};
+
+const DEPTH: usize = 10_000;
+
+#[test]
+fn deep_nesting_does_not_overflow_the_stack() {
+    // Every loop runs once: the innermost one clears the cell that all of them test
+    let source = format!("+{}-{}", "[".repeat(DEPTH), "]".repeat(DEPTH));
+    assert_eq!(max_nesting_depth(&source).unwrap(), DEPTH);
+
+    for opt_level in [OptLevel::None, OptLevel::Basic, OptLevel::Full] {
+        for dispatch in [Dispatch::Match, Dispatch::Table] {
+            let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+                opt_level,
+                dispatch,
+                ..Default::default()
+            })).unwrap();
+
+            interpreter.try_run(&source).unwrap();
+            assert_eq!(interpreter.tape()[0], 0);
+
+            interpreter.source();
+            interpreter.control_flow_graph();
+            interpreter.possibly_infinite_loops();
+            interpreter.estimate_output_len();
+        }
+    }
+}
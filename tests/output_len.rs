@@ -0,0 +1,53 @@
+//! Estimating the output length of programs without running them
+
+use brainfuck_interpreter_rs::{bf_dialect, BFInterpreter, BFInterpreterConfig, OptLevel};
+
+const OPT_LEVELS: [OptLevel; 3] = [OptLevel::None, OptLevel::Basic, OptLevel::Full];
+
+#[test]
+fn estimate_matches_between_opt_levels() {
+    let programs = [
+        ("++++[>.<-]", Some(4)),
+        ("+++[>+<-]>[.-]", Some(3)),
+        // The inner cat loop prints an unknown number of bytes
+        ("+[,[.,]-]", None),
+        (",[.,]", None),
+        ("[.,]+.", Some(1)),
+        ("++[>+++[>.<-]<-]", None),
+    ];
+
+    for (source, expected) in programs {
+        for opt_level in OPT_LEVELS {
+            let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+                opt_level,
+                ..Default::default()
+            })).unwrap();
+            interpreter.load(source).unwrap();
+
+            assert_eq!(interpreter.estimate_output_len(), expected, "{} {:?}", source, opt_level);
+            if let Some(len) = expected {
+                assert_eq!(interpreter.run_bytes(source).unwrap().len(), len);
+            }
+        }
+    }
+}
+
+#[test]
+fn print_pointer_in_a_loop_has_no_estimate() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(bf_dialect! {
+            '>' => PointerInc, '<' => PointerDec, '+' => ByteInc, '-' => ByteDec,
+            '.' => Output, ',' => Input, '[' => OpenLoop, ']' => CloseLoop;
+            '$' => PrintPointer,
+        }),
+        ..Default::default()
+    })).unwrap();
+
+    interpreter.load("+++[$-]").unwrap();
+    assert_eq!(interpreter.estimate_output_len(), None);
+    assert_eq!(interpreter.run("+++[$-]"), "000");
+
+    // Skipped loops print nothing, whatever they contain
+    interpreter.load("[$]+.").unwrap();
+    assert_eq!(interpreter.estimate_output_len(), Some(1));
+}
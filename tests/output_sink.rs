@@ -0,0 +1,45 @@
+//! Running programs for their final tape with the null output sink
+
+use std::{cell::RefCell, rc::Rc};
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, OutputSink};
+
+const PROGRAM: &str = "+++>+++<[>.<-]";
+
+#[test]
+fn null_sink_discards_output_but_runs_the_program() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        output_sink: OutputSink::Null,
+        ..Default::default()
+    })).unwrap();
+
+    assert_eq!(interpreter.run_bytes(PROGRAM).unwrap(), []);
+    assert_eq!(interpreter.output_bytes(), []);
+    assert_eq!(interpreter.tape()[..2], [0, 3]);
+}
+
+#[test]
+fn null_sink_still_limits_output() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        output_sink: OutputSink::Null,
+        max_output: Some(2),
+        ..Default::default()
+    })).unwrap();
+
+    assert!(interpreter.run_bytes(PROGRAM).is_err());
+}
+
+#[test]
+fn callbacks_take_priority_over_the_null_sink() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        output_sink: OutputSink::Null,
+        ..Default::default()
+    })).unwrap();
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let sink = received.clone();
+    interpreter.on_output(move |byte| sink.borrow_mut().push(byte));
+
+    interpreter.run_bytes(PROGRAM).unwrap();
+    assert_eq!(*received.borrow(), [3, 3, 3]);
+}
@@ -0,0 +1,3 @@
+Copies input to output until EOF
+
+,[.,]
@@ -0,0 +1,3 @@
+Reverses its input
+
+>,[>,]<[.<]
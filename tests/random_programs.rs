@@ -0,0 +1,74 @@
+//! Property test: random programs with balanced brackets never make the
+//! interpreter panic, they either finish or fail with a `BFError`.
+//!
+//! Programs come from a small seeded generator so failures are reproducible.
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig, OptLevel};
+
+const CASES: usize = 500;
+const MAX_LEN: usize = 64;
+const MAX_STEPS: u64 = 10_000;
+
+/// xorshift64*, good enough to drive the generator
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Generates a program of at most `MAX_LEN` instructions whose brackets are always balanced
+fn program(rng: &mut Rng) -> String {
+    let len = rng.below(MAX_LEN + 1);
+    let mut source = String::with_capacity(len);
+    let mut depth = 0;
+
+    while source.len() + depth < len {
+        match rng.below(10) {
+            0 => {
+                source.push('[');
+                depth += 1;
+            },
+            1 if depth > 0 => {
+                source.push(']');
+                depth -= 1;
+            },
+            n => source.push(b"><+-.,><+-"[n] as char),
+        }
+    }
+
+    source.extend(std::iter::repeat_n(']', depth));
+    source
+}
+
+#[test]
+fn random_balanced_programs_never_panic() {
+    let mut rng = Rng(0x5EED_CAFE_F00D_BEEF);
+
+    for _ in 0..CASES {
+        let source = program(&mut rng);
+        let input: Vec<u8> = (0..8).map(|_| rng.next() as u8).collect();
+
+        for opt_level in [OptLevel::None, OptLevel::Basic, OptLevel::Full] {
+            let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+                tape_size: Some(32),
+                opt_level,
+                max_steps: Some(MAX_STEPS),
+                block_on_input: false,
+                ..Default::default()
+            })).unwrap();
+            interpreter.set_input(&input);
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| interpreter.try_run(&source)));
+            assert!(result.is_ok(), "{:?} panicked on {}", opt_level, source);
+        }
+    }
+}
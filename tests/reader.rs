@@ -0,0 +1,66 @@
+//! Parsing programs from a reader a block at a time
+
+use std::{collections::HashMap, io};
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig, Instruction};
+
+const HELLO_WORLD: &str = "++++++++++[>+++++++>++++++++++>+++>+<<<<-]>++.>+.+++++++..+++.>++.<<+++++++++++++++.>.+++.------.--------.>+.>.";
+
+/// Hands out one byte per read, so every char and token is cut between reads
+struct ByteByByte<'a>(&'a [u8]);
+
+impl io::Read for ByteByByte<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((&byte, rest)), Some(v)) => {
+                *v = byte;
+                self.0 = rest;
+                Ok(1)
+            },
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn long_single_line_sources_are_read() {
+    // Far longer than a block, without a single newline
+    let source = format!("{}{}{}", "é".repeat(10_000), HELLO_WORLD, "ü".repeat(10_000));
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+
+    assert_eq!(interpreter.run_from_reader(source.as_bytes()).unwrap(), "Hello World!\n");
+    assert_eq!(interpreter.run_from_reader(ByteByByte(source.as_bytes())).unwrap(), "Hello World!\n");
+}
+
+#[test]
+fn tokens_cut_between_reads_are_parsed_whole() {
+    // "inc" is a prefix of "incr", only seeing the whole token tells them apart
+    let tokens = HashMap::from([
+        ("inc".to_string(), Instruction::ByteInc),
+        ("incr".to_string(), Instruction::PointerInc),
+        ("out".to_string(), Instruction::Output),
+    ]);
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_tokens: Some(tokens),
+        ..Default::default()
+    })).unwrap();
+
+    let source = b"incincrinc incrout";
+    assert_eq!(interpreter.run_from_reader(ByteByByte(source)), interpreter.try_run("incincrinc incrout"));
+    assert_eq!(interpreter.instructions(), [
+        Instruction::ByteInc,
+        Instruction::PointerInc,
+        Instruction::ByteInc,
+        Instruction::PointerInc,
+        Instruction::Output,
+    ]);
+}
+
+#[test]
+fn invalid_utf8_is_rejected() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+
+    assert!(matches!(interpreter.run_from_reader(&b"+\xff."[..]), Err(BFError::Io(_))));
+    // A char cut off by the end of the source
+    assert!(matches!(interpreter.run_from_reader(ByteByByte(b"+.\xc3")), Err(BFError::Io(_))));
+}
@@ -0,0 +1,53 @@
+//! Growing and shrinking the tape between runs with `resize_tape`
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig};
+
+fn interpreter(sparse_tape: bool) -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        tape_size: Some(4),
+        fill_value: 7,
+        sparse_tape,
+        ..Default::default()
+    })).unwrap()
+}
+
+#[test]
+fn grown_cells_get_the_fill_value() {
+    for sparse_tape in [false, true] {
+        let mut interpreter = interpreter(sparse_tape);
+        interpreter.run("+");
+
+        interpreter.resize_tape(8).unwrap();
+        assert_eq!(interpreter.tape().into_owned(), [8, 7, 7, 7, 7, 7, 7, 7]);
+
+        // The next run starts on a fresh tape of the new size
+        assert_eq!(interpreter.run_bytes(">>>>>>>."), Ok(vec![7]));
+    }
+}
+
+#[test]
+fn shrinking_drops_the_cells_past_the_end() {
+    let mut interpreter = interpreter(false);
+    interpreter.resize_tape(2).unwrap();
+
+    assert_eq!(interpreter.tape().into_owned(), [7, 7]);
+    assert_eq!(interpreter.try_run(">>"), Err(BFError::PointerOutOfBounds { pointer: 2, index: 1 }));
+}
+
+#[test]
+fn shrinking_below_the_pointer_fails() {
+    let mut interpreter = interpreter(false);
+    interpreter.run(">>>+");
+
+    assert_eq!(interpreter.resize_tape(3), Err(BFError::TapeResizeOutOfBounds { pointer: 3, size: 3 }));
+    assert_eq!(interpreter.tape().into_owned(), [7, 7, 7, 8]);
+
+    // The pointer is reset to the initial pointer on the next run, which must fit too
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        tape_size: Some(4),
+        initial_pointer: 2,
+        ..Default::default()
+    })).unwrap();
+    interpreter.run("<<");
+    assert_eq!(interpreter.resize_tape(2), Err(BFError::TapeResizeOutOfBounds { pointer: 2, size: 2 }));
+}
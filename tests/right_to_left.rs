@@ -0,0 +1,43 @@
+//! Runs programs written from right to left, see `right_to_left`
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig, Instruction};
+
+const HELLO_WORLD: &str = "++++++++++[>+++++++>++++++++++>+++>+<<<<-]>++.>+.+++++++..+++.>++.<<+++++++++++++++.>.+++.------.--------.>+.>.";
+
+fn interpreter() -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        right_to_left: true,
+        ..Default::default()
+    })).unwrap()
+}
+
+#[test]
+fn reversed_programs_run_like_the_original() {
+    let reversed: String = HELLO_WORLD.chars().rev().collect();
+    assert_eq!(interpreter().run(&reversed), "Hello World!\n");
+}
+
+#[test]
+fn brackets_match_in_reading_order() {
+    let mut interpreter = interpreter();
+    interpreter.load("]]-[<+>[+++").unwrap();
+
+    assert_eq!(interpreter.instructions(), [
+        Instruction::ByteInc,
+        Instruction::ByteInc,
+        Instruction::ByteInc,
+        Instruction::OpenLoop,
+        Instruction::PointerInc,
+        Instruction::ByteInc,
+        Instruction::PointerDec,
+        Instruction::OpenLoop,
+        Instruction::ByteDec,
+        Instruction::CloseLoop,
+        Instruction::CloseLoop,
+    ]);
+    assert_eq!(interpreter.matching_bracket(3), Some(10));
+    assert_eq!(interpreter.matching_bracket(7), Some(9));
+
+    // `[-]` read from the right closes a loop before opening it
+    assert_eq!(interpreter.load("[-]"), Err(BFError::UnbalancedBrackets));
+}
@@ -0,0 +1,109 @@
+//! Runs programs on an executor with `run_async`, only built with the `async` feature
+
+#![cfg(feature = "async")]
+
+use std::{cell::RefCell, collections::VecDeque, future::Future, pin::{pin, Pin}, rc::Rc, sync::Arc, task::{Context, Poll, Wake}};
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig, EofBehavior, OnMissingInput, OptLevel, Session};
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Returns `Pending` once before the byte, like input that hasn't arrived yet
+struct Arriving {
+    byte: Option<u8>,
+    arrived: bool,
+}
+
+impl Future for Arriving {
+    type Output = Option<u8>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u8>> {
+        match self.arrived {
+            true => Poll::Ready(self.byte),
+            false => {
+                self.arrived = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            },
+        }
+    }
+}
+
+/// Makes `,` await the bytes of `input`, then EOF
+fn arriving_input(interpreter: &mut BFInterpreter, input: &[u8]) {
+    let input = Rc::new(RefCell::new(input.iter().copied().collect::<VecDeque<u8>>()));
+    interpreter.on_input_async(move || Arriving { byte: input.borrow_mut().pop_front(), arrived: false });
+}
+
+/// Polls the future until it's ready, `run_async` wakes itself whenever it yields
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Arc::new(NoopWaker).into();
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        if let Poll::Ready(v) = future.as_mut().poll(&mut context) {
+            return v;
+        }
+    }
+}
+
+#[test]
+fn async_runs_are_recorded() {
+    let path = std::env::temp_dir().join(format!("bf-async-{}.session", std::process::id()));
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        eof_behavior: EofBehavior::MaxValue,
+        fill_value: 7,
+        ..Default::default()
+    })).unwrap();
+    interpreter.start_recording(&path);
+    interpreter.set_input(b"a");
+
+    let output = block_on(interpreter.run_async(",.>,.>.", 2)).unwrap();
+    assert_eq!(output, "a\u{ff}\u{7}");
+
+    let session = Session::read(&path).unwrap();
+    let mut replay = BFInterpreter::new(Some(session.config())).unwrap();
+    replay.set_input(&session.input);
+    assert_eq!(replay.try_run(&session.source).unwrap(), output);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn input_is_awaited_from_the_async_source() {
+    for opt_level in [OptLevel::None, OptLevel::Full] {
+        let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+            opt_level,
+            eof_behavior: EofBehavior::Zero,
+            ..Default::default()
+        })).unwrap();
+        arriving_input(&mut interpreter, b"cat");
+
+        // `[.,]` is collapsed into a single instruction at `OptLevel::Full`
+        assert_eq!(block_on(interpreter.run_async(",[.,]", 1)).unwrap(), "cat", "{:?}", opt_level);
+    }
+}
+
+#[test]
+fn preloaded_input_is_read_before_the_async_source() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    interpreter.set_input(b"a");
+    arriving_input(&mut interpreter, b"b");
+
+    assert_eq!(block_on(interpreter.run_async(",.,.", 1)).unwrap(), "ab");
+}
+
+#[test]
+fn async_runs_never_block_on_stdin() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        on_missing_input: OnMissingInput::Block,
+        ..Default::default()
+    })).unwrap();
+
+    assert_eq!(block_on(interpreter.run_async("+,", 1)), Err(BFError::NoInput(1)));
+}
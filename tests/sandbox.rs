@@ -0,0 +1,103 @@
+//! Runs misbehaving programs with the sandboxed capabilities
+
+use std::{cell::Cell, rc::Rc};
+
+use brainfuck_interpreter_rs::{bf_dialect, BFError, BFInterpreter, BFInterpreterConfig, Capabilities, Instruction};
+
+fn sandboxed(config: BFInterpreterConfig) -> BFInterpreter {
+    BFInterpreter::new(Some(Capabilities::sandboxed().apply(config))).unwrap()
+}
+
+#[test]
+fn sandbox_runs_well_behaved_programs() {
+    let mut interpreter = sandboxed(BFInterpreterConfig::default());
+    assert_eq!(interpreter.try_run("++++++++[>++++++++<-]>+.").unwrap(), "A");
+}
+
+#[test]
+fn sandbox_stops_misbehaving_programs() {
+    // Without the timeout, which could fire before the step limit on a slow machine
+    let capabilities = Capabilities { max_duration: None, ..Capabilities::sandboxed() };
+    let mut interpreter = BFInterpreter::new(Some(capabilities.apply(BFInterpreterConfig::default()))).unwrap();
+
+    assert_eq!(interpreter.try_run("+[]"), Err(BFError::StepLimitExceeded(10_000_000)));
+    assert_eq!(interpreter.try_run("+[.]"), Err(BFError::OutputLimitExceeded(1 << 20)));
+    assert_eq!(interpreter.try_run("+,"), Err(BFError::InputDisabled(1)));
+    assert!(matches!(interpreter.try_run(&"+".repeat((1 << 20) + 1)), Err(BFError::SourceTooLong { .. })));
+
+    interpreter.set_input(b"preloaded");
+    assert_eq!(interpreter.try_run(","), Err(BFError::InputDisabled(0)));
+}
+
+#[test]
+fn sandbox_disables_side_effects() {
+    let mut interpreter = sandboxed(BFInterpreterConfig {
+        custom_instructions: Some(bf_dialect! {
+            '>' => PointerInc, '<' => PointerDec, '+' => ByteInc, '-' => ByteDec,
+            '.' => Output, ',' => Input, '[' => OpenLoop, ']' => CloseLoop;
+            '!' => Signal, '@' => DumpTape,
+        }),
+        echo_output: true,
+        ..Default::default()
+    });
+
+    assert_eq!(interpreter.try_run("+!"), Err(BFError::SideEffectDisabled { instruction: Instruction::Signal, index: 1 }));
+    assert_eq!(interpreter.try_run("@"), Err(BFError::SideEffectDisabled { instruction: Instruction::DumpTape, index: 0 }));
+}
+
+#[test]
+fn sandbox_only_lowers_the_tape_size() {
+    let config = Capabilities::sandboxed().apply(BFInterpreterConfig { tape_size: Some(10), ..Default::default() });
+    assert_eq!(config.tape_size, Some(10));
+
+    let config = Capabilities::sandboxed().apply(BFInterpreterConfig { tape_size: Some(1 << 30), growable_tape: true, ..Default::default() });
+    assert_eq!((config.tape_size, config.max_tape_size), (Some(65_536), Some(65_536)));
+}
+
+#[test]
+fn largest_source_limit_reads_the_whole_source() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        max_source_len: Some(usize::MAX),
+        ..Default::default()
+    })).unwrap();
+
+    let source = "++++++++[>++++++++<-]>+.";
+    assert_eq!(interpreter.run_from_reader(source.as_bytes()).unwrap(), "A");
+    assert_eq!(interpreter.try_run(source).unwrap(), "A");
+}
+
+#[test]
+fn halts_within_discards_the_output() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+
+    assert_eq!(interpreter.halts_within("++++++++[>++++++++<-]>+.", 1_000), Ok(true));
+    assert!(interpreter.output_bytes().is_empty());
+    assert_eq!(interpreter.halts_within("+[.]", 1_000), Ok(false));
+    assert!(interpreter.output_bytes().is_empty());
+}
+
+#[test]
+fn halts_within_detaches_the_sinks() {
+    let session = std::env::temp_dir().join(format!("bf-halts-within-{}.session", std::process::id()));
+    let bytes = Rc::new(Cell::new(0));
+    let lines = Rc::new(Cell::new(0));
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    interpreter.start_recording(&session);
+
+    let counter = bytes.clone();
+    interpreter.on_output(move |_| counter.set(counter.get() + 1));
+    assert_eq!(interpreter.halts_within("++++++++++.+[.]", 1_000), Ok(false));
+    assert_eq!(bytes.get(), 0);
+
+    let counter = lines.clone();
+    interpreter.on_line(move |_| counter.set(counter.get() + 1));
+    assert_eq!(interpreter.halts_within("++++++++++.", 1_000), Ok(true));
+    assert_eq!(lines.get(), 0);
+    assert!(!session.exists());
+
+    // Attached again afterwards
+    interpreter.run("++++++++++.");
+    assert_eq!(bytes.get(), 1);
+    assert!(session.exists());
+    std::fs::remove_file(session).unwrap();
+}
@@ -0,0 +1,111 @@
+//! Recording runs to session files and replaying them
+
+use brainfuck_interpreter_rs::{bf_dialect, BFError, BFInterpreter, BFInterpreterConfig, CellArithmetic, EofBehavior, PointerUnderflow, Session};
+
+fn session_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("bf-{}-{}.session", name, std::process::id()))
+}
+
+/// Random bytes, input read until EOF and a tape that isn't zeroed, all settings the replay needs
+fn recorded_config() -> BFInterpreterConfig {
+    BFInterpreterConfig {
+        custom_instructions: Some(bf_dialect! {
+            'D' => PointerInc, 'A' => PointerDec, 'W' => ByteInc, 'S' => ByteDec,
+            'O' => Output, 'I' => Input, '(' => OpenLoop, ')' => CloseLoop;
+            'R' => Random,
+        }),
+        eof_behavior: EofBehavior::MaxValue,
+        tape_size: Some(6),
+        fill_value: 7,
+        ..Default::default()
+    }
+}
+
+fn replay(path: &std::path::Path) -> Result<Vec<u8>, BFError> {
+    let session = Session::read(path).unwrap();
+    let mut interpreter = BFInterpreter::new(Some(session.config())).unwrap();
+    interpreter.set_input(&session.input);
+    interpreter.run_bytes(&session.source)
+}
+
+/// Runs `program` with `config` while recording, returns the result of the run and of its replay
+fn record_and_replay(name: &str, config: BFInterpreterConfig, program: &str) -> (Result<Vec<u8>, BFError>, Result<Vec<u8>, BFError>) {
+    let path = session_path(name);
+    let mut interpreter = BFInterpreter::new(Some(config)).unwrap();
+    interpreter.start_recording(&path);
+    interpreter.set_input(b"");
+
+    let recorded = interpreter.run_bytes(program);
+    let replayed = replay(&path);
+    std::fs::remove_file(path).unwrap();
+
+    (recorded, replayed)
+}
+
+const PROGRAM: &str = "RODRODIODIODIODO";
+
+#[test]
+fn replay_matches_the_recorded_run() {
+    let path = session_path("replay");
+    let mut interpreter = BFInterpreter::new(Some(recorded_config())).unwrap();
+    interpreter.start_recording(&path);
+    interpreter.set_input(b"ab");
+
+    let output = interpreter.run_bytes(PROGRAM).unwrap();
+    assert_eq!(output[2..], [b'a', b'b', u8::MAX, 7]);
+    assert_eq!(replay(&path), Ok(output));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn replay_keeps_the_cell_arithmetic() {
+    let config = BFInterpreterConfig { cell_arithmetic: CellArithmetic::Saturating, ..Default::default() };
+    let (recorded, replayed) = record_and_replay("cell-arithmetic", config, "-.");
+
+    assert_eq!(recorded, Ok(vec![0]));
+    assert_eq!(replayed, recorded);
+}
+
+#[test]
+fn replay_keeps_the_pointer_underflow() {
+    let config = BFInterpreterConfig { pointer_underflow: PointerUnderflow::Clamp, ..Default::default() };
+    let (recorded, replayed) = record_and_replay("pointer-underflow", config, "<<+>+<.");
+
+    assert_eq!(recorded, Ok(vec![1]));
+    assert_eq!(replayed, recorded);
+}
+
+#[test]
+fn replay_keeps_the_initial_pointer() {
+    let config = BFInterpreterConfig { initial_pointer: 5, ..Default::default() };
+    let (recorded, replayed) = record_and_replay("initial-pointer", config, "<<<+.");
+
+    assert_eq!(recorded, Ok(vec![1]));
+    assert_eq!(replayed, recorded);
+}
+
+#[test]
+fn replay_keeps_the_growable_tape_and_its_limit() {
+    let config = BFInterpreterConfig { tape_size: Some(2), growable_tape: true, ..Default::default() };
+    let (recorded, replayed) = record_and_replay("growable-tape", config, ">>>+.");
+    assert_eq!(recorded, Ok(vec![1]));
+    assert_eq!(replayed, recorded);
+
+    let config = BFInterpreterConfig { tape_size: Some(2), growable_tape: true, max_tape_size: Some(3), ..Default::default() };
+    let (recorded, replayed) = record_and_replay("max-tape-size", config, ">>>+.");
+    assert_eq!(recorded, Err(BFError::TapeLimitExceeded(3)));
+    assert_eq!(replayed, recorded);
+}
+
+#[test]
+fn replay_keeps_the_input_newline_setting() {
+    let path = session_path("input-newline");
+    let config = BFInterpreterConfig { include_input_newline: true, ..Default::default() };
+    let mut interpreter = BFInterpreter::new(Some(config)).unwrap();
+    interpreter.start_recording(&path);
+    interpreter.run("+.");
+
+    assert!(Session::read(&path).unwrap().config().include_input_newline);
+    std::fs::remove_file(path).unwrap();
+}
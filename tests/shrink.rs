@@ -0,0 +1,44 @@
+//! Shrinking failing programs into small reproductions
+
+use brainfuck_interpreter_rs::{shrink, BFInterpreter, BFInterpreterConfig};
+
+/// Removing instructions can make loops infinite, so every run is capped
+fn interpreter() -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        max_steps: Some(10_000),
+        ..Default::default()
+    })).unwrap()
+}
+
+/// Whether the program prints a 3
+fn prints_three(program: &str) -> bool {
+    let mut interpreter = interpreter();
+    matches!(interpreter.try_run(program), Ok(output) if output.contains('\u{3}'))
+}
+
+#[test]
+fn shrinks_to_a_smaller_program_that_still_matches() {
+    let source = "Hearts! ++>+++<[>.<-] and some more >>+[-]<<";
+    let repro = shrink(source, prints_three);
+
+    assert!(prints_three(&repro));
+    assert!(repro.len() < source.len());
+    assert_eq!(repro, "+++.");
+}
+
+#[test]
+fn keeps_loops_the_predicate_needs() {
+    // Prints 1, 2, 3, so the loop has to stay
+    let prints_two_then_three = |program: &str| {
+        matches!(interpreter().run_bytes(program), Ok(output) if output.windows(2).any(|w| w == [2, 3]))
+    };
+    let repro = shrink("+++[>+.<-]", prints_two_then_three);
+
+    assert!(prints_two_then_three(&repro));
+    assert_eq!(repro.matches('[').count(), repro.matches(']').count());
+}
+
+#[test]
+fn returns_the_source_when_the_predicate_fails() {
+    assert_eq!(shrink("+++.", |_| false), "+++.");
+}
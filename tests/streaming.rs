@@ -0,0 +1,20 @@
+//! Reads the output of a program while stepping through it
+
+use brainfuck_interpreter_rs::BFInterpreter;
+
+const HELLO_WORLD: &str = "++++++++++[>+++++++>++++++++++>+++>+<<<<-]>++.>+.+++++++..+++.>++.<<+++++++++++++++.>.+++.------.--------.>+.>.";
+
+#[test]
+fn output_is_readable_in_the_middle_of_a_run() {
+    let mut interpreter = BFInterpreter::new(None).unwrap();
+    interpreter.load(HELLO_WORLD).unwrap();
+
+    while interpreter.output_bytes().len() < 6 {
+        assert!(interpreter.step().unwrap(), "program ended before printing 6 bytes");
+    }
+    assert_eq!(interpreter.output_bytes(), b"Hello ");
+
+    // The output keeps growing from where it was
+    while interpreter.step().unwrap() {}
+    assert_eq!(interpreter.output_bytes(), b"Hello World!\n");
+}
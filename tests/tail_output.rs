@@ -0,0 +1,62 @@
+//! Keeping only the end of the output with `tail_output`
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig};
+
+/// Prints the bytes 1 to 10
+const COUNT_TO_TEN: &str = "+.+.+.+.+.+.+.+.+.+.";
+
+fn tailed(tail_output: usize) -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        tail_output: Some(tail_output),
+        undo_depth: 100,
+        ..Default::default()
+    })).unwrap()
+}
+
+#[test]
+fn only_the_last_bytes_are_kept() {
+    let mut interpreter = tailed(2);
+    assert_eq!(interpreter.run_bytes(COUNT_TO_TEN), Ok(vec![9, 10]));
+    assert_eq!(interpreter.output_bytes(), [9, 10]);
+
+    let mut interpreter = tailed(0);
+    assert_eq!(interpreter.run_bytes(COUNT_TO_TEN), Ok(vec![]));
+    assert_eq!(interpreter.output_bytes(), []);
+}
+
+#[test]
+fn dropped_bytes_still_count_towards_max_output() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        tail_output: Some(2),
+        max_output: Some(5),
+        ..Default::default()
+    })).unwrap();
+
+    assert_eq!(interpreter.run_bytes(COUNT_TO_TEN), Err(BFError::OutputLimitExceeded(5)));
+    assert_eq!(interpreter.output_bytes(), [4, 5]);
+}
+
+#[test]
+fn stepping_back_shows_the_tail_at_every_step() {
+    let mut interpreter = tailed(2);
+    interpreter.load(COUNT_TO_TEN).unwrap();
+    while interpreter.step().unwrap() {}
+
+    // Every step back over a `.` and its `+` shifts the tail by one byte
+    for end in (2..=9).rev() {
+        interpreter.step_back();
+        interpreter.step_back();
+        assert_eq!(interpreter.output_bytes(), [end - 1, end]);
+    }
+
+    interpreter.step_back();
+    interpreter.step_back();
+    assert_eq!(interpreter.output_bytes(), [1]);
+
+    let mut interpreter = tailed(0);
+    interpreter.load(COUNT_TO_TEN).unwrap();
+    while interpreter.step().unwrap() {}
+    while interpreter.step_back() {
+        assert_eq!(interpreter.output_bytes(), []);
+    }
+}
@@ -0,0 +1,51 @@
+//! Writing the tape to a file and loading it back
+
+use brainfuck_interpreter_rs::{BFInterpreter, BFInterpreterConfig};
+
+fn tape_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("bf-{}-{}.tape", name, std::process::id()))
+}
+
+fn with_tape(tape_size: usize) -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        tape_size: Some(tape_size),
+        ..Default::default()
+    })).unwrap()
+}
+
+#[test]
+fn dumped_tapes_load_back() {
+    let path = tape_path("round-trip");
+    let mut interpreter = with_tape(4);
+    interpreter.run("+>++>>----");
+    interpreter.dump_tape_to(&path).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), [1, 2, 0, 252]);
+
+    let mut loaded = with_tape(4);
+    loaded.load_tape_from(&path).unwrap();
+    assert_eq!(loaded.tape().into_owned(), [1, 2, 0, 252]);
+
+    // Every run starts on the loaded tape
+    assert_eq!(loaded.run_bytes(">."), Ok(vec![2]));
+    assert_eq!(loaded.run_bytes("+."), Ok(vec![2]));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn tapes_longer_than_the_tape_are_rejected() {
+    let path = tape_path("oversize");
+    std::fs::write(&path, [1, 2, 3, 4, 5]).unwrap();
+
+    let mut interpreter = with_tape(4);
+    let error = interpreter.load_tape_from(&path).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(interpreter.tape().into_owned(), [0; 4]);
+
+    // Shorter files only fill the first cells
+    std::fs::write(&path, [9]).unwrap();
+    interpreter.load_tape_from(&path).unwrap();
+    assert_eq!(interpreter.tape().into_owned(), [9, 0, 0, 0]);
+
+    std::fs::remove_file(path).unwrap();
+}
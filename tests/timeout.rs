@@ -0,0 +1,18 @@
+//! Stopping runs that take too long with `max_duration`
+
+use std::time::Duration;
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig};
+
+#[test]
+fn infinite_loops_time_out() {
+    let max_duration = Duration::from_millis(10);
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        max_duration: Some(max_duration),
+        ..Default::default()
+    })).unwrap();
+
+    assert_eq!(interpreter.try_run("+[]"), Err(BFError::Timeout(max_duration)));
+    // The clock starts again on every run
+    assert_eq!(interpreter.try_run("+++[-]"), Ok(String::new()));
+}
@@ -0,0 +1,69 @@
+//! Runs programs written in word based dialects, see `custom_tokens`
+
+use std::collections::HashMap;
+
+use brainfuck_interpreter_rs::{BFError, BFInterpreter, BFInterpreterConfig, Instruction};
+
+/// `in` is a prefix of `inc`, which is a prefix of `incptr`
+fn tokens() -> HashMap<String, Instruction> {
+    HashMap::from([
+        ("incptr".to_string(), Instruction::PointerInc),
+        ("decptr".to_string(), Instruction::PointerDec),
+        ("inc".to_string(), Instruction::ByteInc),
+        ("dec".to_string(), Instruction::ByteDec),
+        ("out".to_string(), Instruction::Output),
+        ("in".to_string(), Instruction::Input),
+        ("loop".to_string(), Instruction::OpenLoop),
+        ("end".to_string(), Instruction::CloseLoop),
+    ])
+}
+
+fn interpreter(ignore_unknown_chars: bool) -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_tokens: Some(tokens()),
+        ignore_unknown_chars,
+        ..Default::default()
+    })).unwrap()
+}
+
+#[test]
+fn multi_char_tokens_run_programs() {
+    let mut interpreter = interpreter(true);
+    let source = "inc inc loop incptr inc inc inc decptr dec end incptr out";
+
+    assert_eq!(interpreter.run_bytes(source), Ok(vec![6]));
+}
+
+#[test]
+fn longest_token_wins() {
+    let mut interpreter = interpreter(true);
+    interpreter.set_input(b"A");
+
+    assert_eq!(interpreter.run_bytes("incincptrinout"), Ok(b"A".to_vec()));
+    assert_eq!(interpreter.instructions(), [
+        Instruction::ByteInc,
+        Instruction::PointerInc,
+        Instruction::Input,
+        Instruction::Output,
+    ]);
+}
+
+#[test]
+fn unknown_text_is_skipped_a_char_at_a_time() {
+    let mut interpreter = interpreter(true);
+
+    // `f`, `o` and `o` are skipped one by one, then `out` matches
+    assert_eq!(interpreter.run_bytes("incfooout"), Ok(vec![1]));
+    // A token can start in the middle of a word
+    assert_eq!(interpreter.run_bytes("xinc out"), Ok(vec![1]));
+}
+
+#[test]
+fn unknown_text_is_rejected_without_ignoring_unknown_chars() {
+    let mut interpreter = interpreter(false);
+
+    assert_eq!(interpreter.run_bytes("incfooout"), Err(BFError::InvalidInstruction { instruction: 'f', index: 3 }));
+    // Separators are unknown chars too
+    assert_eq!(interpreter.run_bytes("inc out"), Err(BFError::InvalidInstruction { instruction: ' ', index: 3 }));
+    assert_eq!(interpreter.run_bytes("incout"), Ok(vec![1]));
+}
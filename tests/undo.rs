@@ -0,0 +1,156 @@
+//! Steps programs forward and back again with the undo log
+
+use std::{cell::Cell, rc::Rc};
+
+use brainfuck_interpreter_rs::{parse_dialect, BFInterpreter, BFInterpreterConfig, EofBehavior, OptLevel};
+
+fn interpreter(opt_level: OptLevel) -> BFInterpreter {
+    BFInterpreter::new(Some(BFInterpreterConfig {
+        opt_level,
+        tape_size: Some(16),
+        eof_behavior: EofBehavior::Zero,
+        undo_depth: 1000,
+        ..Default::default()
+    })).unwrap()
+}
+
+fn state(interpreter: &BFInterpreter) -> (Vec<u8>, usize, Vec<u8>) {
+    (interpreter.tape().to_vec(), interpreter.data_pointer(), interpreter.output_bytes().to_vec())
+}
+
+#[test]
+fn stepping_back_restores_every_previous_state() {
+    let programs = [
+        "++[>++[>+<-]<-]>>.",
+        ">>+++++[>+++++<-]>[>++>+++<<-]>.>.>.",
+        ",[.,]",
+        "+++++[-]+++.>>+>+>+[<]>.",
+    ];
+
+    for source in programs {
+        for opt_level in [OptLevel::None, OptLevel::Full] {
+            let mut interpreter = interpreter(opt_level);
+            interpreter.set_input(b"abc");
+            interpreter.load(source).unwrap();
+
+            let mut states = vec![state(&interpreter)];
+            while interpreter.step().unwrap() {
+                states.push(state(&interpreter));
+            }
+            let last = states.pop().unwrap();
+
+            while let Some(expected) = states.pop() {
+                assert!(interpreter.step_back(), "{:?} ran out of undo entries on {}", opt_level, source);
+                assert_eq!(state(&interpreter), expected, "{:?} diverged stepping back on {}", opt_level, source);
+            }
+            assert!(!interpreter.step_back());
+
+            // Input given back is read again
+            while interpreter.step().unwrap() {}
+            assert_eq!(state(&interpreter), last, "{:?} diverged running again on {}", opt_level, source);
+        }
+    }
+}
+
+#[test]
+fn undo_log_is_bounded() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        undo_depth: 2,
+        ..Default::default()
+    })).unwrap();
+    interpreter.load("+++++").unwrap();
+    while interpreter.step().unwrap() {}
+
+    assert!(interpreter.step_back());
+    assert!(interpreter.step_back());
+    assert!(!interpreter.step_back());
+    assert_eq!(interpreter.tape()[0], 3);
+}
+
+#[test]
+fn replay_reverse_rewinds_to_the_start() {
+    let mut interpreter = interpreter(OptLevel::None);
+    interpreter.run_bytes("++>+").unwrap();
+
+    let states: Vec<String> = interpreter.replay_reverse().collect();
+    assert_eq!(states.len(), 4);
+    assert_eq!(
+        states[0],
+        r#"{"instruction_pointer":3,"data_pointer":1,"steps":3,"loop_depth":0,"cells":[{"index":0,"value":2}]}"#,
+    );
+    assert_eq!(
+        states[3],
+        r#"{"instruction_pointer":0,"data_pointer":0,"steps":0,"loop_depth":0,"cells":[]}"#,
+    );
+
+    assert_eq!(state(&interpreter), (vec![0; 16], 0, vec![]));
+    assert_eq!(interpreter.replay_reverse().count(), 0);
+}
+
+#[test]
+fn stepping_back_restores_output_dropped_by_tail_output() {
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        tail_output: Some(2),
+        undo_depth: 100,
+        ..Default::default()
+    })).unwrap();
+    interpreter.load("+.+.+.+.+.").unwrap();
+    while interpreter.step().unwrap() {}
+    assert_eq!(interpreter.output_bytes(), [4, 5]);
+
+    interpreter.step_back();
+    interpreter.step_back();
+    assert_eq!(interpreter.output_bytes(), [3, 4]);
+
+    while interpreter.step_back() {}
+    assert_eq!(interpreter.output_bytes(), []);
+}
+
+#[test]
+fn stepping_back_keeps_input_a_peek_fetched() {
+    let dialect = "
+'>' = PointerInc
+'<' = PointerDec
+'+' = ByteInc
+'-' = ByteDec
+'.' = Output
+',' = Input
+'[' = OpenLoop
+']' = CloseLoop
+'?' = PeekInput
+";
+    let mut interpreter = BFInterpreter::new(Some(BFInterpreterConfig {
+        custom_instructions: Some(parse_dialect(dialect).unwrap()),
+        undo_depth: 100,
+        ..Default::default()
+    })).unwrap();
+    let calls = Rc::new(Cell::new(0u8));
+    let counter = calls.clone();
+    interpreter.on_input(move || {
+        counter.set(counter.get() + 1);
+        Some(b'0' + counter.get())
+    });
+    interpreter.load("?,>?,").unwrap();
+
+    // Reading the peeked byte again after stepping back doesn't call the callback
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert!(interpreter.step_back());
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.tape()[0], b'1');
+    assert_eq!(calls.get(), 1);
+
+    // Nor does peeking it again
+    while interpreter.step_back() {}
+    while interpreter.step().unwrap() {}
+    assert_eq!(&interpreter.tape()[..2], b"12");
+    assert_eq!(calls.get(), 2);
+
+    // Stepping back over the second peek gives its byte back too
+    interpreter.step_back();
+    interpreter.step_back();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(&interpreter.tape()[..2], b"12");
+    assert_eq!(calls.get(), 2);
+}